@@ -0,0 +1,252 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Sub, SubAssign};
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// Time in whole nanoseconds, so adding up tens of thousands of test cases is exact and the
+/// result does not depend on order of addition
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(u64);
+
+impl Duration {
+    pub const ZERO: Duration = Duration(0);
+    pub const MAX: Duration = Duration(u64::MAX);
+
+    /// Too big values become [MAX]
+    pub const fn from_secs(secs: u64) -> Self {
+        Duration(secs.saturating_mul(NANOS_PER_SEC))
+    }
+
+    /// `None` when value does not fit
+    pub const fn checked_from_secs(secs: u64) -> Option<Self> {
+        match secs > u64::MAX / NANOS_PER_SEC {
+            true => None,
+            false => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Rounds to the nearest nanosecond, negative and NaN become zero, too big values [MAX].
+    /// Meant for values computed by this tool, times read from reports go through
+    /// [Duration::try_from_secs_f64].
+    pub fn from_secs_f64(secs: f64) -> Self {
+        Duration((secs * NANOS_PER_SEC as f64).round() as u64)
+    }
+
+    /// Rounds to the nearest nanosecond, rejects negative, not finite and too big values
+    pub fn try_from_secs_f64(secs: f64) -> Result<Self, String> {
+        let nanos = (secs * NANOS_PER_SEC as f64).round();
+        // Comparisons are false for NaN
+        if nanos >= 0.0 && nanos < u64::MAX as f64 {
+            Ok(Duration(nanos as u64))
+        } else {
+            Err(format!("invalid duration {}s", secs))
+        }
+    }
+
+    pub fn as_secs_f64(self) -> f64 {
+        self.0 as f64 / NANOS_PER_SEC as f64
+    }
+
+    /// Whole seconds, half a second rounds up
+    pub const fn round_secs(self) -> u64 {
+        self.0 / NANOS_PER_SEC + (self.0 % NANOS_PER_SEC >= NANOS_PER_SEC / 2) as u64
+    }
+
+    pub const fn checked_add(self, other: Duration) -> Option<Duration> {
+        match self.0.checked_add(other.0) {
+            Some(nanos) => Some(Duration(nanos)),
+            None => None,
+        }
+    }
+
+    pub const fn saturating_sub(self, other: Duration) -> Duration {
+        Duration(self.0.saturating_sub(other.0))
+    }
+
+    /// How many times `other` fits into `self`
+    pub fn ratio(self, other: Duration) -> f64 {
+        self.0 as f64 / other.0 as f64
+    }
+}
+
+/// Seconds, the unit of every report format
+impl From<f64> for Duration {
+    fn from(secs: f64) -> Self {
+        Duration::from_secs_f64(secs)
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, other: Duration) -> Duration {
+        Duration(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, other: Duration) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Duration {
+    type Output = Duration;
+
+    /// Stops at zero, like [Add] stops at [Duration::MAX]
+    fn sub(self, other: Duration) -> Duration {
+        self.saturating_sub(other)
+    }
+}
+
+impl SubAssign for Duration {
+    fn sub_assign(&mut self, other: Duration) {
+        *self = *self - other;
+    }
+}
+
+impl Div<u32> for Duration {
+    type Output = Duration;
+
+    fn div(self, divisor: u32) -> Duration {
+        Duration(self.0 / u64::from(divisor))
+    }
+}
+
+impl Sum for Duration {
+    fn sum<I: Iterator<Item = Duration>>(iter: I) -> Duration {
+        iter.fold(Duration::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Duration> for Duration {
+    fn sum<I: Iterator<Item = &'a Duration>>(iter: I) -> Duration {
+        iter.copied().sum()
+    }
+}
+
+/// Optional milliseconds, the unit of JavaScript test runners
+pub fn optional_millis<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Option::<f64>::deserialize(deserializer)?
+        .map(|millis| Duration::try_from_secs_f64(millis / 1000.0).map_err(D::Error::custom))
+        .transpose()
+}
+
+/// Rounded to whole seconds: `45s`, `2m 05s`, `1h 02m 03s`
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.round_secs();
+        let (hours, minutes, seconds) = (secs / 3600, secs / 60 % 60, secs % 60);
+        if hours > 0 {
+            write!(f, "{}h {:02}m {:02}s", hours, minutes, seconds)
+        } else if minutes > 0 {
+            write!(f, "{}m {:02}s", minutes, seconds)
+        } else {
+            write!(f, "{}s", seconds)
+        }
+    }
+}
+
+/// Written as seconds, the unit of JSON documents printed by this tool and of timing store
+impl Serialize for Duration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.as_secs_f64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Duration::try_from_secs_f64(f64::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sum_is_exact() {
+        //given
+        let times = vec![Duration::from_secs_f64(0.1); 100_000];
+
+        //when
+        let total: Duration = times.iter().sum();
+
+        //then
+        assert_eq!(total, Duration::from_secs(10_000));
+        assert_eq!(total.as_secs_f64(), 10_000.0);
+    }
+
+    #[test]
+    fn from_secs_f64() {
+        //then
+        assert_eq!(Duration::from_secs_f64(39.218), Duration(39_218_000_000));
+        assert_eq!(Duration::from_secs_f64(-1.0), Duration::ZERO);
+        assert_eq!(Duration::from_secs_f64(f64::NAN), Duration::ZERO);
+        assert_eq!(Duration::from_secs_f64(f64::INFINITY), Duration::MAX);
+    }
+
+    #[test]
+    fn try_from_secs_f64() {
+        //then
+        assert_eq!(
+            Duration::try_from_secs_f64(39.218),
+            Ok(Duration(39_218_000_000))
+        );
+        assert_eq!(Duration::try_from_secs_f64(0.0), Ok(Duration::ZERO));
+        assert_eq!(
+            Duration::try_from_secs_f64(-1.0),
+            Err(String::from("invalid duration -1s"))
+        );
+        assert!(Duration::try_from_secs_f64(f64::NAN).is_err());
+        assert!(Duration::try_from_secs_f64(f64::INFINITY).is_err());
+        assert!(Duration::try_from_secs_f64(1e11).is_err());
+        assert!(serde_json::from_str::<Duration>("-5").is_err());
+    }
+
+    #[test]
+    fn arithmetic_does_not_overflow() {
+        //then
+        assert_eq!(Duration::from_secs(u64::MAX), Duration::MAX);
+        assert_eq!(Duration::checked_from_secs(u64::MAX), None);
+        assert_eq!(
+            Duration::checked_from_secs(2),
+            Some(Duration(2_000_000_000))
+        );
+        assert_eq!(
+            Duration::from_secs(1) - Duration::from_secs(2),
+            Duration::ZERO
+        );
+        assert_eq!(Duration::MAX + Duration::from_secs(1), Duration::MAX);
+    }
+
+    #[test]
+    fn human_format() {
+        //then
+        assert_eq!(Duration::ZERO.to_string(), "0s");
+        assert_eq!(Duration::from_secs_f64(0.4).to_string(), "0s");
+        assert_eq!(Duration::from_secs_f64(2.5).to_string(), "3s");
+        assert_eq!(Duration::from_secs(59).to_string(), "59s");
+        assert_eq!(Duration::from_secs(125).to_string(), "2m 05s");
+        assert_eq!(Duration::from_secs(3723).to_string(), "1h 02m 03s");
+        assert_eq!(Duration::from_secs(90_000).to_string(), "25h 00m 00s");
+    }
+
+    #[test]
+    fn serialized_as_seconds() {
+        //when
+        let json = serde_json::to_string(&Duration::from_secs_f64(13.5)).unwrap();
+
+        //then
+        assert_eq!(json, "13.5");
+        assert_eq!(
+            serde_json::from_str::<Duration>(&json).unwrap(),
+            Duration::from_secs_f64(13.5)
+        );
+    }
+}
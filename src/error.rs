@@ -0,0 +1,150 @@
+use std::fmt;
+use std::io::{self, Read};
+
+/// Why an input was skipped
+#[derive(Debug)]
+pub enum ReportError {
+    /// Directory given on command line could not be searched
+    Search { path: String, message: String },
+    /// File could not be opened or read
+    Read { path: String, source: io::Error },
+    /// No registered format recognizes the file
+    UnknownFormat { path: String, root: Option<String> },
+    /// File looks like a known format but is not valid
+    Parse {
+        path: String,
+        format: &'static str,
+        message: String,
+        /// Line and column, both starting from 1, when parser knows where it failed
+        position: Option<(usize, usize)>,
+    },
+    /// Line of timing store which is not a valid record
+    Record {
+        path: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::Search { path, message } => {
+                write!(f, "{}: can't search for reports: {}", path, message)
+            }
+            ReportError::Read { path, source } => write!(f, "{}: can't read: {}", path, source),
+            ReportError::UnknownFormat {
+                path,
+                root: Some(root),
+            } => write!(f, "{}: unknown root element <{}>", path, root),
+            ReportError::UnknownFormat { path, root: None } => {
+                write!(f, "{}: not a report of any known format", path)
+            }
+            ReportError::Parse {
+                path,
+                format,
+                message,
+                position: Some((line, column)),
+            } => write!(
+                f,
+                "{}:{}:{}: invalid {} report: {}",
+                path, line, column, format, message
+            ),
+            ReportError::Parse {
+                path,
+                format,
+                message,
+                position: None,
+            } => write!(f, "{}: invalid {} report: {}", path, format, message),
+            ReportError::Record {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: invalid timing record: {}", path, line, message),
+        }
+    }
+}
+
+impl std::error::Error for ReportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReportError::Read { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Error of format parser, with byte offset in report when parser knows it
+#[derive(Debug, PartialEq)]
+pub struct FormatError {
+    pub message: String,
+    pub offset: Option<u64>,
+}
+
+impl From<String> for FormatError {
+    fn from(message: String) -> Self {
+        FormatError {
+            message,
+            offset: None,
+        }
+    }
+}
+
+/// Line and column of byte offset, both starting from 1
+pub fn line_column(content: impl io::Read, offset: u64) -> io::Result<(usize, usize)> {
+    let mut line = 1;
+    let mut column = 1;
+    for byte in io::BufReader::new(content.take(offset)).bytes() {
+        match byte? {
+            b'\n' => {
+                line += 1;
+                column = 1;
+            }
+            // Continuation bytes of UTF-8 do not start a new character
+            byte if byte & 0b1100_0000 != 0b1000_0000 => column += 1,
+            _ => {}
+        }
+    }
+    Ok((line, column))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_and_column_of_offset() {
+        //given
+        let content = "<a>\n  <żb>\n</a>";
+
+        //then
+        assert_eq!(line_column(content.as_bytes(), 0).unwrap(), (1, 1));
+        assert_eq!(line_column(content.as_bytes(), 6).unwrap(), (2, 3));
+        assert_eq!(line_column(content.as_bytes(), 9).unwrap(), (2, 5));
+    }
+
+    #[test]
+    fn display_errors() {
+        //given
+        let parse = ReportError::Parse {
+            path: String::from("TEST-a.xml"),
+            format: "JUnit",
+            message: String::from("missing attribute time of testsuite"),
+            position: Some((2, 5)),
+        };
+        let unknown = ReportError::UnknownFormat {
+            path: String::from("index.html"),
+            root: Some(String::from("html")),
+        };
+
+        //then
+        assert_eq!(
+            parse.to_string(),
+            "TEST-a.xml:2:5: invalid JUnit report: missing attribute time of testsuite"
+        );
+        assert_eq!(
+            unknown.to_string(),
+            "index.html: unknown root element <html>"
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use crate::duration::Duration;
+use clap::ValueEnum;
+
+/// How observations of one test from many runs become its expected duration
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Estimator {
+    /// Duration from the latest run
+    Last,
+    /// Arithmetic mean of all runs
+    #[default]
+    Mean,
+    /// Middle value, not moved by a few extreme runs
+    Median,
+    /// 90th percentile, plans for slow runs rather than typical ones
+    P90,
+    /// Exponentially weighted moving average, recent runs weigh more
+    Ewma,
+}
+
+/// Estimator with its settings
+#[derive(Debug, Clone)]
+pub struct Estimation {
+    pub estimator: Estimator,
+    /// Number of runs after which weight of observation in [Estimator::Ewma] halves
+    pub half_life: f64,
+    /// Observations further from median than this many median absolute deviations are ignored
+    pub outlier_threshold: Option<f64>,
+}
+
+impl Default for Estimation {
+    fn default() -> Self {
+        Estimation {
+            estimator: Estimator::default(),
+            half_life: 5.0,
+            outlier_threshold: None,
+        }
+    }
+}
+
+impl Estimation {
+    /// Expected duration from observations ordered from the oldest, zero without observations
+    pub fn estimate(&self, observations: &[Duration]) -> Duration {
+        let kept = match self.outlier_threshold {
+            Some(threshold) => without_outliers(observations, threshold),
+            None => observations.to_vec(),
+        };
+        if kept.is_empty() {
+            return Duration::ZERO;
+        }
+        match self.estimator {
+            Estimator::Last => kept[kept.len() - 1],
+            Estimator::Mean => kept.iter().sum::<Duration>() / kept.len() as u32,
+            Estimator::Median => median(&kept),
+            Estimator::P90 => {
+                let sorted = sorted(&kept);
+                // Nearest rank
+                sorted[(sorted.len() * 9).div_ceil(10) - 1]
+            }
+            Estimator::Ewma => {
+                let (weighted, weights) = kept.iter().rev().enumerate().fold(
+                    (0.0, 0.0),
+                    |(weighted, weights), (age, time)| {
+                        let weight = 0.5f64.powf(age as f64 / self.half_life);
+                        (weighted + weight * time.as_secs_f64(), weights + weight)
+                    },
+                );
+                Duration::from_secs_f64(weighted / weights)
+            }
+        }
+    }
+}
+
+fn sorted(observations: &[Duration]) -> Vec<Duration> {
+    let mut sorted = observations.to_vec();
+    sorted.sort();
+    sorted
+}
+
+fn median(observations: &[Duration]) -> Duration {
+    let sorted = sorted(observations);
+    let middle = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2
+    } else {
+        sorted[middle]
+    }
+}
+
+/// Smallest deviation used for outliers, as a fraction of median
+const MIN_RELATIVE_DEVIATION: f64 = 0.1;
+
+/// Keeps observations within `threshold` median absolute deviations from median. Deviation is
+/// at least [MIN_RELATIVE_DEVIATION] of median, so when most observations are equal a slightly
+/// different one is not an outlier.
+fn without_outliers(observations: &[Duration], threshold: f64) -> Vec<Duration> {
+    if observations.len() < 3 {
+        return observations.to_vec();
+    }
+    let center = median(observations);
+    let distance = |time: Duration| time.max(center) - time.min(center);
+    let deviations: Vec<Duration> = observations.iter().map(|t| distance(*t)).collect();
+    let deviation = median(&deviations)
+        .as_secs_f64()
+        .max(center.as_secs_f64() * MIN_RELATIVE_DEVIATION);
+    let limit = deviation * threshold;
+    observations
+        .iter()
+        .copied()
+        .filter(|time| distance(*time).as_secs_f64() <= limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seconds(times: &[u64]) -> Vec<Duration> {
+        times.iter().map(|t| Duration::from_secs(*t)).collect()
+    }
+
+    fn estimate(estimator: Estimator, times: &[u64]) -> Duration {
+        Estimation {
+            estimator,
+            ..Default::default()
+        }
+        .estimate(&seconds(times))
+    }
+
+    #[test]
+    fn estimators() {
+        //given
+        let times = [10, 12, 11, 40, 13];
+
+        //then
+        assert_eq!(estimate(Estimator::Last, &times), Duration::from_secs(13));
+        assert_eq!(
+            estimate(Estimator::Mean, &times),
+            Duration::from_secs_f64(17.2)
+        );
+        assert_eq!(estimate(Estimator::Median, &times), Duration::from_secs(12));
+        assert_eq!(estimate(Estimator::P90, &times), Duration::from_secs(40));
+        assert_eq!(
+            estimate(Estimator::Median, &[1, 2, 3, 4]),
+            Duration::from_secs_f64(2.5)
+        );
+        assert_eq!(
+            estimate(Estimator::P90, &(1..=20).collect::<Vec<_>>()),
+            Duration::from_secs(18)
+        );
+    }
+
+    #[test]
+    fn every_estimator_handles_no_and_single_observation() {
+        for estimator in Estimator::value_variants() {
+            //then
+            assert_eq!(estimate(*estimator, &[]), Duration::ZERO, "{:?}", estimator);
+            assert_eq!(
+                estimate(*estimator, &[7]),
+                Duration::from_secs(7),
+                "{:?}",
+                estimator
+            );
+        }
+    }
+
+    #[test]
+    fn ewma_weighs_recent_runs_more() {
+        //given
+        let estimation = |half_life| Estimation {
+            estimator: Estimator::Ewma,
+            half_life,
+            outlier_threshold: None,
+        };
+
+        //when
+        let result = estimation(1.0).estimate(&seconds(&[10, 20]));
+        let long_memory = estimation(1e9).estimate(&seconds(&[10, 20]));
+
+        //then
+        // Weights 0.5 and 1
+        assert_eq!(result, Duration::from_secs_f64(50.0 / 3.0));
+        assert_eq!(long_memory.round_secs(), 15);
+    }
+
+    #[test]
+    fn outliers_are_ignored() {
+        //given
+        let estimation = Estimation {
+            estimator: Estimator::Mean,
+            outlier_threshold: Some(3.0),
+            ..Default::default()
+        };
+
+        //then
+        assert_eq!(
+            estimation.estimate(&seconds(&[10, 12, 11, 40, 13])),
+            Duration::from_secs_f64(11.5)
+        );
+        assert_eq!(
+            estimation.estimate(&seconds(&[10, 10, 10, 90])),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            estimation.estimate(&seconds(&[10, 10, 10, 11])),
+            Duration::from_secs_f64(10.25)
+        );
+        assert_eq!(
+            estimation.estimate(&seconds(&[0, 0, 0, 1])),
+            Duration::from_secs(0)
+        );
+        assert_eq!(
+            estimation.estimate(&seconds(&[10, 90])),
+            Duration::from_secs(50)
+        );
+    }
+}
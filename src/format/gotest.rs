@@ -0,0 +1,185 @@
+//! Event stream of `go test -json` (test2json). Packages become suites, top level tests become
+//! test cases, subtests are part of their parent test as `go test -run` selects by parent.
+//! Packages without tests are left out.
+
+use crate::duration::Duration;
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Event {
+    action: String,
+    #[serde(default)]
+    package: String,
+    #[serde(default)]
+    test: Option<String>,
+    #[serde(default)]
+    elapsed: Option<f64>,
+    #[serde(default)]
+    output: Option<String>,
+}
+
+#[derive(Default)]
+struct Package {
+    elapsed: Option<Duration>,
+    tests: BTreeMap<String, TestCase>,
+    output: BTreeMap<String, String>,
+}
+
+/// Streams have `Action` in every event
+pub fn is_go_test(content: &str) -> bool {
+    content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<Event>(line).ok())
+        .is_some()
+}
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    let mut packages: BTreeMap<String, Package> = BTreeMap::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let at_line = |e: String| format!("line {}: {}", number + 1, e);
+        let event = serde_json::from_str::<Event>(line).map_err(|e| at_line(e.to_string()))?;
+        let elapsed = event
+            .elapsed
+            .map(Duration::try_from_secs_f64)
+            .transpose()
+            .map_err(at_line)?;
+        let package = packages.entry(event.package).or_default();
+        let test = match event.test {
+            Some(test) if !test.contains('/') => test,
+            Some(_) => continue,
+            None => {
+                if matches!(event.action.as_str(), "pass" | "fail" | "skip") {
+                    package.elapsed = elapsed;
+                }
+                continue;
+            }
+        };
+        let outcome = match event.action.as_str() {
+            "output" => {
+                let output = package.output.entry(test).or_default();
+                output.push_str(&event.output.unwrap_or_default());
+                continue;
+            }
+            "pass" => Outcome::Passed,
+            "fail" => Outcome::Failed(OutcomeDetails {
+                text: package.output.remove(&test).unwrap_or_default(),
+                ..Default::default()
+            }),
+            "skip" => Outcome::Skipped(OutcomeDetails::default()),
+            _ => continue,
+        };
+        package.output.remove(&test);
+        package.tests.insert(
+            test.clone(),
+            TestCase {
+                name: test,
+                time: elapsed.unwrap_or_default(),
+                classname: String::new(),
+                outcome,
+            },
+        );
+    }
+    Ok(packages
+        .into_iter()
+        .filter(|(_, package)| !package.tests.is_empty())
+        .map(|(name, package)| {
+            let test_cases: Vec<TestCase> = package
+                .tests
+                .into_values()
+                .map(|tc| TestCase {
+                    classname: name.clone(),
+                    ..tc
+                })
+                .collect();
+            TestSuite {
+                time: package
+                    .elapsed
+                    .unwrap_or_else(|| test_cases.iter().map(|tc| tc.time).sum()),
+                name,
+                test_cases,
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+
+pub struct GoTest;
+
+impl ReportFormat for GoTest {
+    fn name(&self) -> &'static str {
+        "go test -json"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["go-test*.json", "gotest*.json"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.is_none() && is_go_test(sniff.content)
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{"Time":"2024-10-18T20:40:34.1+02:00","Action":"start","Package":"example.com/calc"}
+{"Time":"2024-10-18T20:40:34.2+02:00","Action":"run","Package":"example.com/calc","Test":"TestAdd"}
+{"Time":"2024-10-18T20:40:34.2+02:00","Action":"output","Package":"example.com/calc","Test":"TestAdd","Output":"=== RUN   TestAdd\n"}
+{"Time":"2024-10-18T20:40:34.2+02:00","Action":"run","Package":"example.com/calc","Test":"TestAdd/negative"}
+{"Time":"2024-10-18T20:40:35.2+02:00","Action":"pass","Package":"example.com/calc","Test":"TestAdd/negative","Elapsed":1}
+{"Time":"2024-10-18T20:40:35.7+02:00","Action":"pass","Package":"example.com/calc","Test":"TestAdd","Elapsed":1.5}
+{"Time":"2024-10-18T20:40:35.7+02:00","Action":"run","Package":"example.com/calc","Test":"TestDivide"}
+{"Time":"2024-10-18T20:40:35.7+02:00","Action":"output","Package":"example.com/calc","Test":"TestDivide","Output":"    calc_test.go:12: expected 2, got 3\n"}
+{"Time":"2024-10-18T20:40:35.9+02:00","Action":"fail","Package":"example.com/calc","Test":"TestDivide","Elapsed":0.25}
+{"Time":"2024-10-18T20:40:36.0+02:00","Action":"fail","Package":"example.com/calc","Elapsed":2}
+{"Time":"2024-10-18T20:40:36.0+02:00","Action":"output","Package":"example.com/util","Output":"?   \texample.com/util\t[no test files]\n"}
+{"Time":"2024-10-18T20:40:36.0+02:00","Action":"skip","Package":"example.com/util","Elapsed":0}
+"#;
+
+    #[test]
+    fn detects_go_test() {
+        //then
+        assert!(is_go_test(REPORT));
+        assert!(!is_go_test(r#"{ "type": "suite", "event": "started" }"#));
+    }
+
+    #[test]
+    fn parses_packages_as_suites() {
+        //when
+        let result = parse(REPORT).unwrap();
+
+        //then
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name, "example.com/calc");
+        assert_eq!(result[0].time.as_secs_f64(), 2.0);
+        let names: Vec<&str> = result[0]
+            .test_cases
+            .iter()
+            .map(|tc| tc.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["TestAdd", "TestDivide"]);
+        assert_eq!(result[0].test_cases[0].time.as_secs_f64(), 1.5);
+        assert_eq!(result[0].test_cases[0].classname, "example.com/calc");
+        assert_eq!(
+            result[0].test_cases[1].outcome,
+            Outcome::Failed(OutcomeDetails {
+                text: String::from("    calc_test.go:12: expected 2, got 3\n"),
+                ..Default::default()
+            })
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Report of `jest --json`, also written by Vitest `--reporter=json`. Test files become suites,
+//! times in report are milliseconds. Test files are written with absolute paths, suites are named
+//! by path relative to project root, so they match on machines with other checkout directory.
+
+use crate::duration::{optional_millis, Duration};
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use serde_derive::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    #[serde(default)]
+    root_dir: Option<PathBuf>,
+    test_results: Vec<FileResult>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileResult {
+    name: String,
+    #[serde(default)]
+    start_time: Option<f64>,
+    #[serde(default)]
+    end_time: Option<f64>,
+    #[serde(default)]
+    perf_stats: Option<PerfStats>,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    assertion_results: Vec<AssertionResult>,
+}
+
+#[derive(Deserialize)]
+struct PerfStats {
+    #[serde(default, deserialize_with = "optional_millis")]
+    runtime: Option<Duration>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AssertionResult {
+    full_name: String,
+    status: String,
+    #[serde(default, deserialize_with = "optional_millis")]
+    duration: Option<Duration>,
+    #[serde(default)]
+    failure_messages: Vec<String>,
+}
+
+/// Object with `testResults` array, nothing else at top level is required
+pub fn is_jest(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content)
+        .ok()
+        .and_then(|value| value.get("testResults").map(|v| v.is_array()))
+        .unwrap_or(false)
+}
+
+impl From<AssertionResult> for TestCase {
+    fn from(result: AssertionResult) -> Self {
+        let details = || OutcomeDetails {
+            message: result.failure_messages.first().cloned(),
+            text: result.failure_messages.join("\n"),
+            ..Default::default()
+        };
+        let outcome = match result.status.as_str() {
+            "failed" => Outcome::Failed(details()),
+            "pending" | "skipped" | "todo" | "disabled" => Outcome::Skipped(details()),
+            _ => Outcome::Passed,
+        };
+        TestCase {
+            time: result.duration.unwrap_or_default(),
+            name: result.full_name,
+            outcome,
+            ..Default::default()
+        }
+    }
+}
+
+/// Path relative to `root`, with `/` separators. Paths outside of root are kept.
+fn relative(path: &str, root: &Path) -> String {
+    match Path::new(path).strip_prefix(root) {
+        Ok(relative) if root.is_absolute() => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        _ => path.to_string(),
+    }
+}
+
+/// Test files are made relative to `rootDir` of report when it has one, otherwise to working
+/// directory, where the runner is usually started
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    let report = serde_json::from_str::<Report>(content).map_err(|e| e.to_string())?;
+    let root = report
+        .root_dir
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    Ok(report
+        .test_results
+        .into_iter()
+        .map(|mut file| {
+            file.name = relative(&file.name, &root);
+            let test_cases: Vec<TestCase> = file
+                .assertion_results
+                .into_iter()
+                .map(TestCase::from)
+                .map(|tc| TestCase {
+                    classname: file.name.clone(),
+                    ..tc
+                })
+                .collect();
+            // File time includes module loading and hooks, which test durations miss
+            let runtime = file.perf_stats.and_then(|stats| stats.runtime).or(
+                match (file.start_time, file.end_time) {
+                    (Some(start), Some(end)) => {
+                        Duration::try_from_secs_f64((end - start) / 1000.0).ok()
+                    }
+                    _ => None,
+                },
+            );
+            let time = runtime.unwrap_or_else(|| test_cases.iter().map(|tc| tc.time).sum());
+            let warnings = match file.message.is_empty() || !test_cases.is_empty() {
+                true => Vec::new(),
+                false => vec![format!("test file {} failed: {}", file.name, file.message)],
+            };
+            TestSuite {
+                name: file.name,
+                time,
+                test_cases,
+                warnings,
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+
+pub struct Jest;
+
+impl ReportFormat for Jest {
+    fn name(&self) -> &'static str {
+        "Jest/Vitest JSON"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["jest*.json", "vitest*.json"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.is_none() && is_jest(sniff.content)
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{"numFailedTestSuites":1,"numFailedTests":1,"numPassedTests":1,"numPendingTests":1,"numTotalTests":3,"startTime":1729276834000,"success":false,
+"testResults":[
+  {"name":"src/calc.test.js","status":"failed","message":"","startTime":1729276834000,"endTime":1729276836500,
+   "assertionResults":[
+     {"ancestorTitles":["calc"],"fullName":"calc adds","status":"passed","title":"adds","duration":1000,"failureMessages":[]},
+     {"ancestorTitles":["calc"],"fullName":"calc divides","status":"failed","title":"divides","duration":500,"failureMessages":["Error: expect(received).toBe(expected)"]}
+   ]},
+  {"name":"src/slow.test.js","status":"passed","message":"","perfStats":{"start":1729276834000,"end":1729276834250,"runtime":250},
+   "assertionResults":[
+     {"ancestorTitles":[],"fullName":"waits","status":"pending","title":"waits","duration":null,"failureMessages":[]}
+   ]}
+]}"#;
+
+    #[test]
+    fn detects_jest() {
+        //then
+        assert!(is_jest(REPORT));
+        assert!(!is_jest(r#"{"stats":{},"tests":[]}"#));
+    }
+
+    #[test]
+    fn parses_files_as_suites() {
+        //when
+        let result = parse(REPORT).unwrap();
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "src/calc.test.js");
+        assert_eq!(result[0].time.as_secs_f64(), 2.5);
+        assert_eq!(result[0].test_cases[0].name, "calc adds");
+        assert_eq!(result[0].test_cases[0].classname, "src/calc.test.js");
+        assert_eq!(result[0].test_cases[0].time.as_secs_f64(), 1.0);
+        assert!(result[0].test_cases[1].is_failed());
+        assert_eq!(result[1].time.as_secs_f64(), 0.25);
+        assert!(result[1].test_cases[0].is_skipped());
+        assert!(result[0].warnings.is_empty());
+    }
+
+    #[test]
+    fn absolute_paths_are_relative_to_root() {
+        //given
+        let content = r#"{"rootDir":"/home/ci/build","testResults":[
+  {"name":"/home/ci/build/src/calc.test.js","assertionResults":[
+    {"fullName":"calc adds","status":"passed","duration":1000}
+  ]},
+  {"name":"/opt/shared/other.test.js","assertionResults":[]}
+]}"#;
+        let working_dir = std::env::current_dir().unwrap();
+        let in_working_dir = format!(
+            r#"{{"testResults":[{{"name":{},"assertionResults":[]}}]}}"#,
+            serde_json::to_string(&working_dir.join("src").join("app.test.tsx")).unwrap()
+        );
+
+        //when
+        let result = parse(content).unwrap();
+        let without_root = parse(&in_working_dir).unwrap();
+
+        //then
+        assert_eq!(result[0].name, "src/calc.test.js");
+        assert_eq!(result[0].test_cases[0].classname, "src/calc.test.js");
+        assert_eq!(result[1].name, "/opt/shared/other.test.js");
+        assert_eq!(without_root[0].name, "src/app.test.tsx");
+    }
+
+    #[test]
+    fn failed_file_without_tests_is_warning() {
+        //given
+        let content = r#"{"testResults":[{"name":"src/broken.test.js","message":"SyntaxError","assertionResults":[]}]}"#;
+
+        //when
+        let result = parse(content).unwrap();
+
+        //then
+        assert_eq!(
+            result[0].warnings,
+            vec![String::from(
+                "test file src/broken.test.js failed: SyntaxError"
+            )]
+        );
+    }
+}
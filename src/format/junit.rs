@@ -0,0 +1,693 @@
+//! Plain JUnit XML, with `<testsuite>` or `<testsuites>` root, as written by Maven Surefire,
+//! Gradle and most other tools. Nested suites are flattened.
+
+use crate::duration::Duration;
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{
+    parse_time, Outcome, OutcomeDetails, RawSuiteCase, RawTestSuite, TestCase, TestSuite,
+    TestSuites,
+};
+use quick_xml::de::from_str;
+use quick_xml::escape::unescape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::io::{self, BufRead, Read};
+use std::str::FromStr;
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    if crate::format::root_element(content).as_deref() == Some("testsuites") {
+        let test_suites = from_str::<TestSuites>(content).map_err(|e| e.to_string())?;
+        let parents: Vec<String> = test_suites.name.into_iter().collect();
+        return Ok(test_suites
+            .test_suites
+            .into_iter()
+            .flat_map(|ts| ts.flatten(&parents))
+            .collect());
+    }
+    Ok(from_str::<TestSuite>(content)
+        .map_err(|e| e.to_string())?
+        .flatten(&[]))
+}
+
+/// Value of attribute, unescaped
+fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>, String> {
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(|e| e.to_string())?;
+        if attribute.key.local_name().as_ref() == name.as_bytes() {
+            let value = attribute.unescape_value().map_err(|e| e.to_string())?;
+            return Ok(Some(value.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn required(element: &BytesStart, name: &str) -> Result<String, String> {
+    attribute(element, name)?.ok_or_else(|| {
+        format!(
+            "missing attribute {} of {}",
+            name,
+            String::from_utf8_lossy(element.local_name().as_ref())
+        )
+    })
+}
+
+fn parsed<T: FromStr>(element: &BytesStart, name: &str) -> Result<Option<T>, String>
+where
+    T::Err: ToString,
+{
+    attribute(element, name)?
+        .map(|value| value.trim().parse::<T>().map_err(|e| e.to_string()))
+        .transpose()
+}
+
+/// Time in seconds, see [parse_time]
+fn time(element: &BytesStart) -> Result<Option<Duration>, String> {
+    attribute(element, "time")?
+        .map(|value| parse_time(&value).ok_or_else(|| format!("invalid time {}", value)))
+        .transpose()
+}
+
+fn suite(element: &BytesStart) -> Result<RawTestSuite, String> {
+    Ok(RawTestSuite {
+        name: required(element, "name")?,
+        time: time(element)?,
+        tests: parsed(element, "tests")?,
+        failures: parsed(element, "failures")?,
+        errors: parsed(element, "errors")?,
+        skipped: parsed(element, "skipped")?,
+        ..Default::default()
+    })
+}
+
+/// Longest text of `<failure>`, `<error>` or `<skipped>` kept, the rest is dropped
+const MAX_DETAILS_TEXT: usize = 64 * 1024;
+
+/// Source which can look a few bytes ahead, so [content] can stop right before end tag and leave
+/// it to XML reader
+struct Lookahead<R> {
+    inner: R,
+    ahead: Vec<u8>,
+}
+
+impl<R: BufRead> Lookahead<R> {
+    /// Next `count` bytes without consuming them, fewer only at the end of input
+    fn peek(&mut self, count: usize) -> io::Result<&[u8]> {
+        while self.ahead.len() < count {
+            let available = self.inner.fill_buf()?;
+            if available.is_empty() {
+                break;
+            }
+            let taken = available.len().min(count - self.ahead.len());
+            self.ahead.extend_from_slice(&available[..taken]);
+            self.inner.consume(taken);
+        }
+        Ok(&self.ahead[..count.min(self.ahead.len())])
+    }
+}
+
+impl<R: BufRead> Read for Lookahead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let amount = available.len().min(buf.len());
+        buf[..amount].copy_from_slice(&available[..amount]);
+        self.consume(amount);
+        Ok(amount)
+    }
+}
+
+impl<R: BufRead> BufRead for Lookahead<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self.ahead.is_empty() {
+            true => self.inner.fill_buf(),
+            false => Ok(&self.ahead),
+        }
+    }
+
+    fn consume(&mut self, amount: usize) {
+        match self.ahead.is_empty() {
+            true => self.inner.consume(amount),
+            false => drop(self.ahead.drain(..amount)),
+        }
+    }
+}
+
+/// Part of element content being read by [content]
+enum Markup {
+    Text,
+    CData,
+    Comment,
+    /// Tag of nested element, `first` is byte after `<`, `previous` the last one read
+    Tag {
+        first: Option<u8>,
+        previous: u8,
+        quote: Option<u8>,
+    },
+}
+
+/// Reads content of element whose start tag was just read, including its end tag. Works on bytes
+/// of the source instead of events, so a huge output or CDATA is never held in memory: at most
+/// `limit` bytes of text are kept, text of nested elements is skipped.
+fn content<R: BufRead>(
+    reader: &mut Reader<Lookahead<R>>,
+    name: &[u8],
+    limit: usize,
+) -> Result<String, String> {
+    let end = [b"</", name].concat();
+    let mut text = String::new();
+    // Raw text since the last markup, or content of CDATA
+    let mut segment: Vec<u8> = Vec::new();
+    let mut markup = Markup::Text;
+    let mut depth = 0;
+    let mut tail = [0u8; 2];
+    loop {
+        let mut stream = reader.stream();
+        let chunk = stream.fill_buf().map_err(|e| e.to_string())?;
+        if chunk.is_empty() {
+            return Err(String::from("unexpected end of file"));
+        }
+        let room = limit.saturating_sub(text.len() + segment.len());
+        let used = match &mut markup {
+            Markup::Text => match chunk.iter().position(|b| *b == b'<') {
+                Some(0) => {
+                    let ahead = stream
+                        .get_mut()
+                        .peek(end.len().max(9) + 1)
+                        .map_err(|e| e.to_string())?;
+                    let closes = ahead.starts_with(&end)
+                        && ahead
+                            .get(end.len())
+                            .is_some_and(|b| *b == b'>' || b.is_ascii_whitespace());
+                    let (next, used) = if depth == 0 && closes {
+                        push_text(&mut text, &mut segment)?;
+                        break;
+                    } else if ahead.starts_with(b"<![CDATA[") {
+                        (Markup::CData, 9)
+                    } else if ahead.starts_with(b"<!--") {
+                        (Markup::Comment, 4)
+                    } else {
+                        (
+                            Markup::Tag {
+                                first: None,
+                                previous: b'<',
+                                quote: None,
+                            },
+                            1,
+                        )
+                    };
+                    if depth == 0 {
+                        push_text(&mut text, &mut segment)?;
+                    }
+                    tail = [0; 2];
+                    markup = next;
+                    used
+                }
+                found => {
+                    let used = found.unwrap_or(chunk.len());
+                    if depth == 0 {
+                        segment.extend_from_slice(&chunk[..used.min(room)]);
+                    }
+                    used
+                }
+            },
+            Markup::CData | Markup::Comment => {
+                let cdata = matches!(markup, Markup::CData);
+                let closing = if cdata { [b']'; 2] } else { [b'-'; 2] };
+                let found = chunk.iter().position(|b| {
+                    let closed = *b == b'>' && tail == closing;
+                    tail = [tail[1], *b];
+                    closed
+                });
+                let used = found.map_or(chunk.len(), |i| i + 1);
+                if depth == 0 && cdata {
+                    segment.extend_from_slice(&chunk[..used.min(room)]);
+                }
+                if found.is_some() {
+                    if segment.ends_with(b"]]>") {
+                        segment.truncate(segment.len() - 3);
+                    }
+                    text.push_str(&String::from_utf8_lossy(&segment));
+                    segment.clear();
+                    markup = Markup::Text;
+                }
+                used
+            }
+            Markup::Tag {
+                first,
+                previous,
+                quote,
+            } => {
+                let found = chunk.iter().position(|b| {
+                    let closed = *b == b'>' && quote.is_none();
+                    match quote {
+                        Some(q) if q == b => *quote = None,
+                        None if matches!(b, b'"' | b'\'') => *quote = Some(*b),
+                        _ => {}
+                    }
+                    if !closed {
+                        first.get_or_insert(*b);
+                        *previous = *b;
+                    }
+                    closed
+                });
+                if found.is_some() {
+                    match (*first, *previous) {
+                        (Some(b'/'), _) => depth -= 1,
+                        (Some(b'!') | Some(b'?'), _) | (_, b'/') => {}
+                        _ => depth += 1,
+                    }
+                    markup = Markup::Text;
+                }
+                found.map_or(chunk.len(), |i| i + 1)
+            }
+        };
+        stream.consume(used);
+    }
+    reader
+        .read_event_into(&mut Vec::new())
+        .map_err(|e| e.to_string())?;
+    Ok(text)
+}
+
+/// Adds text read since the last markup, without surrounding whitespace
+fn push_text(text: &mut String, segment: &mut Vec<u8>) -> Result<(), String> {
+    // Text cut by limit may end in the middle of an entity
+    if let Some(amp) = segment.iter().rposition(|b| *b == b'&') {
+        if !segment[amp..].contains(&b';') {
+            segment.truncate(amp);
+        }
+    }
+    let raw = String::from_utf8_lossy(segment);
+    text.push_str(unescape(&raw).map_err(|e| e.to_string())?.trim());
+    segment.clear();
+    Ok(())
+}
+
+fn details(element: &BytesStart) -> Result<OutcomeDetails, String> {
+    Ok(OutcomeDetails {
+        message: attribute(element, "message")?,
+        kind: attribute(element, "type")?,
+        text: String::new(),
+    })
+}
+
+fn test_case<R: BufRead>(
+    reader: &mut Reader<Lookahead<R>>,
+    element: &BytesStart,
+    empty: bool,
+) -> Result<RawSuiteCase, String> {
+    let name = required(element, "name")?;
+    let time = time(element)?;
+    let classname = required(element, "classname")?;
+    // Error wins over failure and failure over skip
+    let (error, failure, skipped) = match empty {
+        true => (None, None, None),
+        false => outcomes(reader)?,
+    };
+    let outcome = error
+        .map(Outcome::Errored)
+        .or(failure.map(Outcome::Failed))
+        .or(skipped.map(Outcome::Skipped))
+        .unwrap_or(Outcome::Passed);
+    Ok(RawSuiteCase {
+        test_case: TestCase {
+            name,
+            time: time.unwrap_or_default(),
+            classname,
+            outcome,
+        },
+        timed: time.is_some(),
+    })
+}
+
+type Outcomes = (
+    Option<OutcomeDetails>,
+    Option<OutcomeDetails>,
+    Option<OutcomeDetails>,
+);
+
+/// First error, failure and skip among children of test case, outputs are skipped
+fn outcomes<R: BufRead>(reader: &mut Reader<Lookahead<R>>) -> Result<Outcomes, String> {
+    let (mut error, mut failure, mut skipped) = (None, None, None);
+    let mut buf = Vec::new();
+    loop {
+        let (element, start) = match reader
+            .read_event_into(&mut buf)
+            .map_err(|e| e.to_string())?
+        {
+            Event::Start(element) => (element, true),
+            Event::Empty(element) => (element, false),
+            Event::End(_) => break,
+            Event::Eof => return Err(String::from("unexpected end of file")),
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+        let element = element.into_owned();
+        let outcome = match element.local_name().as_ref() {
+            b"error" => Some(&mut error),
+            b"failure" => Some(&mut failure),
+            b"skipped" => Some(&mut skipped),
+            _ => None,
+        };
+        match outcome {
+            Some(outcome) => {
+                let mut details = details(&element)?;
+                if start {
+                    details.text = content(reader, element.name().as_ref(), MAX_DETAILS_TEXT)?;
+                }
+                outcome.get_or_insert(details);
+            }
+            // Output of test, often the biggest part of report
+            None if start => drop(content(reader, element.name().as_ref(), 0)?),
+            None => {}
+        }
+        buf.clear();
+    }
+    Ok((error, failure, skipped))
+}
+
+/// Same as [parse], but reads report event by event and skips outputs at byte level, so memory
+/// use does not grow with the size of the report or of any text in it
+pub fn parse_stream(source: impl BufRead) -> Result<Vec<TestSuite>, FormatError> {
+    let mut reader = Reader::from_reader(Lookahead {
+        inner: source,
+        ahead: Vec::new(),
+    });
+    read_suites(&mut reader).map_err(|message| FormatError {
+        message,
+        offset: Some(reader.buffer_position()),
+    })
+}
+
+fn read_suites<R: BufRead>(reader: &mut Reader<Lookahead<R>>) -> Result<Vec<TestSuite>, String> {
+    let mut buf = Vec::new();
+    let mut parents: Vec<String> = Vec::new();
+    // Suites which are not closed yet, outermost first
+    let mut open: Vec<RawTestSuite> = Vec::new();
+    let mut result = Vec::new();
+    loop {
+        let (element, start) = match reader
+            .read_event_into(&mut buf)
+            .map_err(|e| e.to_string())?
+        {
+            Event::Start(element) => (element, true),
+            Event::Empty(element) => (element, false),
+            Event::End(element) => {
+                if element.local_name().as_ref() == b"testsuite" {
+                    let closed = TestSuite::from(open.pop().ok_or("unexpected end of testsuite")?);
+                    match open.last_mut() {
+                        Some(parent) => parent.test_suites.push(closed),
+                        None => result.extend(closed.flatten(&parents)),
+                    }
+                }
+                buf.clear();
+                continue;
+            }
+            Event::Eof => break,
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+        let element = element.into_owned();
+        match element.local_name().as_ref() {
+            b"testsuites" => parents = attribute(&element, "name")?.into_iter().collect(),
+            b"testsuite" => {
+                let suite = suite(&element)?;
+                match (start, open.last_mut()) {
+                    (true, _) => open.push(suite),
+                    (false, Some(parent)) => parent.test_suites.push(TestSuite::from(suite)),
+                    (false, None) => result.extend(TestSuite::from(suite).flatten(&parents)),
+                }
+            }
+            b"testcase" => {
+                let test_case = test_case(reader, &element, !start)?;
+                open.last_mut()
+                    .ok_or("testcase outside of testsuite")?
+                    .test_cases
+                    .push(test_case);
+            }
+            // Properties and outputs of suite
+            _ if start => drop(content(reader, element.name().as_ref(), 0)?),
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(result)
+}
+
+pub struct JUnit;
+
+impl ReportFormat for JUnit {
+    fn name(&self) -> &'static str {
+        "JUnit"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["TEST*.xml"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        matches!(
+            sniff.root.as_deref(),
+            Some("testsuites") | Some("testsuite")
+        )
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+
+    fn streams(&self) -> bool {
+        true
+    }
+
+    fn parse_reader(&self, reader: &mut dyn BufRead) -> Result<Vec<TestSuite>, FormatError> {
+        parse_stream(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    const SUITE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuite name="scenario.SearchTest" tests="4" skipped="1" failures="1" errors="1" time="39.218">
+    <properties>
+        <property name="java.version" value="21"/>
+    </properties>
+    <testcase name="testSearchQuery" classname="scenario.SearchTest" time="16.274"/>
+    <testcase name="testSearch &quot;regex&quot;" classname="scenario.SearchTest" time="10.609">
+        <failure message="expected &lt;1&gt; but was &lt;2&gt;" type="java.lang.AssertionError"><![CDATA[at scenario.SearchTest.testSearchRegex(SearchTest.java:12)]]></failure>
+        <system-out><![CDATA[lots of output <testcase name="fake"/>]]></system-out>
+    </testcase>
+    <testcase name="testSearchString" classname="scenario.SearchTest" time="11.391">
+        <error message="boom" type="java.lang.NullPointerException">
+            trace &amp; more
+        </error>
+    </testcase>
+    <testcase name="testSkipped" classname="scenario.SearchTest" time="0.944">
+        <skipped message="not today"/>
+    </testcase>
+    <system-out><![CDATA[suite output]]></system-out>
+    <system-err><![CDATA[]]></system-err>
+</testsuite>"#;
+
+    const NESTED: &str = r#"<testsuites name="all">
+    <testsuite name="module" time="7.0">
+        <testcase name="testOwn" classname="module" time="1.0"/>
+        <testsuite name="scenario.SearchTest" time="2.0">
+            <testcase name="testSearch" classname="scenario.SearchTest" time="2.0"/>
+        </testsuite>
+        <testsuite name="inner" time="4.0">
+            <testsuite name="scenario.LoginTest" time="4.0">
+                <testcase name="testLogin" classname="scenario.LoginTest" time="4.0">
+                    <skipped/>
+                </testcase>
+            </testsuite>
+            <testsuite name="scenario.EmptyTest" time="0.0"/>
+        </testsuite>
+    </testsuite>
+    <testsuite name="scenario.OtherTest" tests="1" time="1.5">
+        <testcase name="testOther" classname="scenario.OtherTest" time="1.5"/>
+    </testsuite>
+</testsuites>"#;
+
+    #[test]
+    fn stream_equals_serde_for_suite() {
+        //when
+        let expected = parse(SUITE).unwrap();
+        let result = parse_stream(SUITE.as_bytes()).unwrap();
+
+        //then
+        assert_eq!(result, expected);
+        assert_eq!(result[0].test_cases[1].name, "testSearch \"regex\"");
+        assert_eq!(result[0].test_cases.len(), 4);
+    }
+
+    #[test]
+    fn stream_equals_serde_for_nested_suites() {
+        //when
+        let expected = parse(NESTED).unwrap();
+        let result = parse_stream(NESTED.as_bytes()).unwrap();
+
+        //then
+        assert_eq!(result, expected);
+        assert_eq!(result.len(), 5);
+        assert_eq!(
+            result[2].parents,
+            vec![
+                String::from("all"),
+                String::from("module"),
+                String::from("inner")
+            ]
+        );
+    }
+
+    #[test]
+    fn stream_rejects_invalid_time() {
+        //when
+        let result = parse_stream(r#"<testsuite name="a" time="soon"/>"#.as_bytes());
+
+        //then
+        assert_eq!(
+            result.unwrap_err(),
+            FormatError {
+                message: String::from("invalid time soon"),
+                offset: Some(33),
+            }
+        );
+    }
+
+    #[test]
+    fn stream_equals_serde_for_lenient_times() {
+        //given
+        let content = r#"<testsuites>
+    <testsuite name="a.Untimed" tests="2">
+        <testcase name="t1" classname="a.Untimed" time="1,234.5"/>
+        <testcase name="t2" classname="a.Untimed" time="12,5"/>
+    </testsuite>
+    <testsuite name="a.Units" time="2s">
+        <testcase name="t1" classname="a.Units" time="250ms"/>
+        <testcase name="t2" classname="a.Units" time="1 750,0"/>
+    </testsuite>
+</testsuites>"#;
+
+        //when
+        let expected = parse(content).unwrap();
+        let result = parse_stream(content.as_bytes()).unwrap();
+
+        //then
+        assert_eq!(result, expected);
+        assert_eq!(result[0].time.as_secs_f64(), 1247.0);
+        assert_eq!(
+            result[0].warnings,
+            vec![String::from(
+                "suite a.Untimed has no time, using sum of its test cases 1247s"
+            )]
+        );
+        assert_eq!(result[1].time.as_secs_f64(), 2.0);
+        assert_eq!(result[1].test_cases[0].time.as_secs_f64(), 0.25);
+        assert_eq!(result[1].test_cases[1].time.as_secs_f64(), 1750.0);
+        assert!(result[1].warnings.is_empty());
+    }
+
+    #[test]
+    fn stream_equals_serde_for_test_case_without_time() {
+        //given
+        let content = r#"<testsuite name="a.Partly" time="3.0">
+    <testcase name="t1" classname="a.Partly" time="3.0"/>
+    <testcase name="t2" classname="a.Partly">
+        <skipped/>
+    </testcase>
+</testsuite>"#;
+
+        //when
+        let expected = parse(content).unwrap();
+        let result = parse_stream(content.as_bytes()).unwrap();
+
+        //then
+        assert_eq!(result, expected);
+        assert_eq!(result[0].test_cases.len(), 2);
+        assert_eq!(result[0].test_cases[1].time, Duration::default());
+        assert_eq!(
+            result[0].warnings,
+            vec![String::from(
+                "test case t2 of suite a.Partly has no time, using 0s"
+            )]
+        );
+    }
+
+    #[test]
+    fn stream_reads_report_in_small_chunks() {
+        //given
+        let cases: String = (0..1000)
+            .map(|i| {
+                format!(
+                    r#"<testcase name="t{}" classname="A" time="0.5"><system-out>{}</system-out></testcase>"#,
+                    i,
+                    "x".repeat(1000)
+                )
+            })
+            .collect();
+        let content = format!(r#"<testsuite name="A" time="500">{}</testsuite>"#, cases);
+
+        //when
+        let result =
+            parse_stream(std::io::BufReader::with_capacity(64, content.as_bytes())).unwrap();
+
+        //then
+        assert_eq!(result[0].test_cases.len(), 1000);
+        assert_eq!(result, parse(&content).unwrap());
+    }
+
+    #[test]
+    fn stream_skips_markup_inside_outputs() {
+        //given
+        let content = r#"<testsuite name="A" time="1">
+    <testcase name="t" classname="A" time="1">
+        <system-out><![CDATA[</system-out> -->]]><!-- ]]> </system-out> --><line at=">">text</line><br/></system-out>
+        <failure message="m">trace &amp; more<detail>ignored</detail><![CDATA[ <cdata> ]]></failure >
+    </testcase>
+</testsuite>"#;
+
+        //when
+        let result = parse_stream(BufReader::with_capacity(8, content.as_bytes())).unwrap();
+
+        //then
+        match &result[0].test_cases[0].outcome {
+            Outcome::Failed(details) => assert_eq!(details.text, "trace & more <cdata> "),
+            outcome => panic!("unexpected outcome {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn content_skips_cdata_comments_and_attributes() {
+        //given
+        let source = r#"<out>a &amp; <![CDATA[</out> ]]> b<!-- </out> --><out at=">" to='</out>'>nested</out><br/></out ><next/>"#;
+        let reader = |source: &'static str| {
+            let mut reader = Reader::from_reader(Lookahead {
+                inner: BufReader::with_capacity(4, source.as_bytes()),
+                ahead: Vec::new(),
+            });
+            reader.read_event_into(&mut Vec::new()).unwrap();
+            reader
+        };
+        let mut full = reader(source);
+        let mut limited = reader(source);
+
+        //when
+        let text = content(&mut full, b"out", 1024).unwrap();
+        let mut buf = Vec::new();
+        let next = full.read_event_into(&mut buf).unwrap();
+        let short = content(&mut limited, b"out", 3).unwrap();
+
+        //then
+        assert_eq!(text, "a &</out> b");
+        assert!(matches!(next, Event::Empty(e) if e.name().as_ref() == b"next"));
+        assert_eq!(short, "a</");
+    }
+}
@@ -0,0 +1,194 @@
+//! Event stream of libtest `--format json` (with `--report-time` for durations). Stream does not
+//! name test binaries, suites are named after the report file by the parser.
+
+use crate::duration::Duration;
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use serde_derive::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Event {
+    Suite {
+        event: String,
+        exec_time: Option<f64>,
+    },
+    Test {
+        event: String,
+        name: String,
+        exec_time: Option<f64>,
+        stdout: Option<String>,
+        message: Option<String>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Streams start with `{ "type": "suite", ...`
+pub fn is_libtest(content: &str) -> bool {
+    content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<Event>(line).ok())
+        .is_some_and(|event| !matches!(event, Event::Other))
+}
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    let mut result = Vec::new();
+    let mut current: Option<TestSuite> = None;
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let at_line = |e: String| format!("line {}: {}", number + 1, e);
+        let event = serde_json::from_str::<Event>(line).map_err(|e| at_line(e.to_string()))?;
+        let time = |exec_time: Option<f64>| {
+            exec_time
+                .map(Duration::try_from_secs_f64)
+                .transpose()
+                .map_err(at_line)
+        };
+        match event {
+            Event::Suite { event, .. } if event == "started" => {
+                current = Some(TestSuite::default());
+            }
+            Event::Suite { exec_time, .. } => {
+                if let Some(mut suite) = current.take() {
+                    suite.time = time(exec_time)?
+                        .unwrap_or_else(|| suite.test_cases.iter().map(|tc| tc.time).sum());
+                    result.push(suite);
+                }
+            }
+            Event::Test {
+                event,
+                name,
+                exec_time,
+                stdout,
+                message,
+            } => {
+                let details = || OutcomeDetails {
+                    message,
+                    kind: None,
+                    text: stdout.unwrap_or_default(),
+                };
+                let outcome = match event.as_str() {
+                    "started" => continue,
+                    "ok" => Outcome::Passed,
+                    "failed" | "timeout" => Outcome::Failed(details()),
+                    "ignored" => Outcome::Skipped(details()),
+                    _ => continue,
+                };
+                current
+                    .get_or_insert_with(TestSuite::default)
+                    .test_cases
+                    .push(TestCase {
+                        name,
+                        time: time(exec_time)?.unwrap_or_default(),
+                        classname: String::new(),
+                        outcome,
+                    });
+            }
+            Event::Other => {}
+        }
+    }
+    // Stream cut before end of suite, keep what was reported
+    if let Some(mut suite) = current {
+        suite.time = suite.test_cases.iter().map(|tc| tc.time).sum();
+        result.push(suite);
+    }
+    Ok(result)
+}
+
+pub struct Libtest;
+
+impl ReportFormat for Libtest {
+    fn name(&self) -> &'static str {
+        "libtest JSON"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["libtest*.json", "cargo-test*.json"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.is_none() && is_libtest(sniff.content)
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "tests::parses" }
+{ "type": "test", "name": "tests::parses", "event": "ok", "exec_time": 0.25 }
+{ "type": "test", "event": "started", "name": "tests::fails" }
+{ "type": "test", "name": "tests::fails", "event": "failed", "exec_time": 0.5, "stdout": "thread panicked" }
+{ "type": "test", "event": "started", "name": "tests::slow" }
+{ "type": "test", "name": "tests::slow", "event": "ignored" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1, "measured": 0, "filtered_out": 0, "exec_time": 0.8 }
+{ "type": "suite", "event": "started", "test_count": 1 }
+{ "type": "test", "event": "started", "name": "cli_works" }
+{ "type": "test", "name": "cli_works", "event": "ok", "exec_time": 1.5 }
+"#;
+
+    #[test]
+    fn detects_libtest() {
+        //then
+        assert!(is_libtest(REPORT));
+        assert!(!is_libtest("{\"Action\":\"run\"}"));
+        assert!(!is_libtest("<testsuite/>"));
+    }
+
+    #[test]
+    fn parses_suites_and_tests() {
+        //when
+        let result = parse(REPORT).unwrap();
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].time.as_secs_f64(), 0.8);
+        assert_eq!(result[0].test_cases.len(), 3);
+        assert_eq!(result[0].test_cases[0].name, "tests::parses");
+        assert_eq!(result[0].test_cases[0].time.as_secs_f64(), 0.25);
+        assert_eq!(
+            result[0].test_cases[1].outcome,
+            Outcome::Failed(OutcomeDetails {
+                message: None,
+                kind: None,
+                text: String::from("thread panicked"),
+            })
+        );
+        assert!(result[0].test_cases[2].is_skipped());
+        assert_eq!(result[1].time.as_secs_f64(), 1.5);
+        assert_eq!(result[1].test_cases[0].name, "cli_works");
+    }
+
+    #[test]
+    fn reports_invalid_line() {
+        //when
+        let result = parse("{ \"type\": \"suite\", \"event\": \"started\" }\nnot json");
+
+        //then
+        assert!(result.unwrap_err().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn reports_invalid_time() {
+        //given
+        let content = r#"{ "type": "suite", "event": "started", "test_count": 1 }
+{ "type": "test", "name": "slow", "event": "ok", "exec_time": -1.5 }
+{ "type": "suite", "event": "ok", "exec_time": 1e300 }"#;
+
+        //when
+        let result = parse(content);
+
+        //then
+        assert_eq!(result.unwrap_err(), "line 2: invalid duration -1.5s");
+    }
+}
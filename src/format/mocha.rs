@@ -0,0 +1,150 @@
+//! Report of `mocha --reporter json`. Tests carry their file, files become suites and times in
+//! report are milliseconds.
+
+use crate::duration::{optional_millis, Duration};
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Deserialize)]
+struct Report {
+    tests: Vec<MochaTest>,
+    #[serde(default)]
+    pending: Vec<MochaTest>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MochaTest {
+    full_title: String,
+    #[serde(default)]
+    file: String,
+    #[serde(default, deserialize_with = "optional_millis")]
+    duration: Option<Duration>,
+    #[serde(default)]
+    err: Value,
+}
+
+/// Object with `stats` and `tests`
+pub fn is_mocha(content: &str) -> bool {
+    serde_json::from_str::<Value>(content)
+        .ok()
+        .is_some_and(|value| value.get("stats").is_some() && value.get("tests").is_some())
+}
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    let report = serde_json::from_str::<Report>(content).map_err(|e| e.to_string())?;
+    let pending: HashSet<(&str, &str)> = report
+        .pending
+        .iter()
+        .map(|test| (test.file.as_str(), test.full_title.as_str()))
+        .collect();
+    let mut files: BTreeMap<&str, Vec<TestCase>> = BTreeMap::new();
+    for test in &report.tests {
+        // Passed tests have empty `err` object
+        let message = test.err.get("message").and_then(Value::as_str);
+        let outcome = if pending.contains(&(test.file.as_str(), test.full_title.as_str())) {
+            Outcome::Skipped(OutcomeDetails::default())
+        } else if let Some(message) = message {
+            Outcome::Failed(OutcomeDetails {
+                message: Some(message.to_string()),
+                text: test
+                    .err
+                    .get("stack")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                ..Default::default()
+            })
+        } else {
+            Outcome::Passed
+        };
+        files.entry(&test.file).or_default().push(TestCase {
+            name: test.full_title.clone(),
+            time: test.duration.unwrap_or_default(),
+            classname: test.file.clone(),
+            outcome,
+        });
+    }
+    Ok(files
+        .into_iter()
+        .map(|(file, test_cases)| TestSuite {
+            name: file.to_string(),
+            time: test_cases.iter().map(|tc| tc.time).sum(),
+            test_cases,
+            ..Default::default()
+        })
+        .collect())
+}
+
+pub struct Mocha;
+
+impl ReportFormat for Mocha {
+    fn name(&self) -> &'static str {
+        "Mocha JSON"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["mocha*.json"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.is_none() && is_mocha(sniff.content)
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{
+  "stats": {"suites": 2, "tests": 3, "passes": 1, "pending": 1, "failures": 1, "duration": 1800},
+  "tests": [
+    {"title": "adds", "fullTitle": "calc adds", "file": "test/calc.spec.js", "duration": 1250, "currentRetry": 0, "speed": "slow", "err": {}},
+    {"title": "divides", "fullTitle": "calc divides", "file": "test/calc.spec.js", "duration": 500, "currentRetry": 0, "err": {"message": "expected 2 to equal 3", "stack": "AssertionError: expected 2 to equal 3"}},
+    {"title": "waits", "fullTitle": "slow waits", "file": "test/slow.spec.js", "currentRetry": 0, "err": {}}
+  ],
+  "pending": [
+    {"title": "waits", "fullTitle": "slow waits", "file": "test/slow.spec.js", "currentRetry": 0, "err": {}}
+  ],
+  "failures": [],
+  "passes": []
+}"#;
+
+    #[test]
+    fn detects_mocha() {
+        //then
+        assert!(is_mocha(REPORT));
+        assert!(!is_mocha(r#"{"testResults":[]}"#));
+    }
+
+    #[test]
+    fn parses_files_as_suites() {
+        //when
+        let result = parse(REPORT).unwrap();
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "test/calc.spec.js");
+        assert_eq!(result[0].time.as_secs_f64(), 1.75);
+        assert_eq!(result[0].test_cases[0].name, "calc adds");
+        assert_eq!(result[0].test_cases[0].outcome, Outcome::Passed);
+        assert_eq!(
+            result[0].test_cases[1].outcome,
+            Outcome::Failed(OutcomeDetails {
+                message: Some(String::from("expected 2 to equal 3")),
+                kind: None,
+                text: String::from("AssertionError: expected 2 to equal 3"),
+            })
+        );
+        assert_eq!(result[1].name, "test/slow.spec.js");
+        assert!(result[1].test_cases[0].is_skipped());
+    }
+}
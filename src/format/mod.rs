@@ -0,0 +1,152 @@
+//! Report formats, each turned into [crate::model::TestSuite]. A file is parsed by the first
+//! format in [FORMATS] which recognizes it.
+
+use crate::error::FormatError;
+use crate::model::TestSuite;
+use glob::Pattern;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::BufRead;
+
+pub mod gotest;
+pub mod jest;
+pub mod junit;
+pub mod libtest;
+pub mod mocha;
+pub mod nextest;
+pub mod nunit;
+pub mod pytest;
+pub mod tap;
+pub mod trx;
+
+/// What is known about a file before it is parsed
+pub struct Sniff<'a> {
+    pub content: &'a str,
+    /// Extension of file name without dot
+    pub extension: Option<&'a str>,
+    /// Name of the first XML element, `None` when content is not XML
+    pub root: Option<String>,
+}
+
+impl<'a> Sniff<'a> {
+    pub fn new(content: &'a str, extension: Option<&'a str>) -> Self {
+        Sniff {
+            content,
+            extension,
+            root: root_element(content),
+        }
+    }
+}
+
+pub trait ReportFormat: Sync {
+    /// Name used in messages
+    fn name(&self) -> &'static str;
+
+    /// Glob patterns of file names the format is written to by default, searched for when
+    /// no `--include` is given. Formats printed to standard output get conventional names.
+    fn file_patterns(&self) -> &'static [&'static str];
+
+    fn detect(&self, sniff: &Sniff) -> bool;
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError>;
+
+    /// Whether [ReportFormat::parse_reader] avoids loading whole report into memory
+    fn streams(&self) -> bool {
+        false
+    }
+
+    fn parse_reader(&self, reader: &mut dyn BufRead) -> Result<Vec<TestSuite>, FormatError> {
+        let mut content = String::new();
+        reader
+            .read_to_string(&mut content)
+            .map_err(|e| e.to_string())?;
+        self.parse(&content)
+    }
+}
+
+/// Registered formats, variants before the format they extend
+pub static FORMATS: &[&dyn ReportFormat] = &[
+    &nextest::Nextest,
+    &junit::JUnit,
+    &trx::Trx,
+    &nunit::NUnit,
+    &jest::Jest,
+    &mocha::Mocha,
+    &libtest::Libtest,
+    &pytest::Pytest,
+    &gotest::GoTest,
+    &tap::Tap,
+];
+
+pub fn detect(sniff: &Sniff) -> Option<&'static dyn ReportFormat> {
+    FORMATS.iter().copied().find(|format| format.detect(sniff))
+}
+
+/// File name patterns of all registered formats
+pub fn file_patterns() -> Vec<Pattern> {
+    FORMATS
+        .iter()
+        .flat_map(|format| format.file_patterns())
+        .map(|pattern| Pattern::new(pattern).unwrap())
+        .collect()
+}
+
+/// Name of the first element in document, declaration and comments are skipped. Text before
+/// it means the content is not XML, like a TAP stream mentioning `<tags>`.
+pub fn root_element(content: &str) -> Option<String> {
+    let mut reader = Reader::from_str(content);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                return Some(String::from_utf8_lossy(e.local_name().as_ref()).into_owned())
+            }
+            Ok(Event::Text(e)) if !e.iter().all(u8::is_ascii_whitespace) => return None,
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_formats() {
+        //given
+        let detected = |content: &str, extension: Option<&str>| {
+            detect(&Sniff::new(content, extension)).map(|format| format.name())
+        };
+
+        //then
+        assert_eq!(detected("<testsuite/>", None), Some("JUnit"));
+        assert_eq!(
+            detected(r#"<testsuites uuid="x"/>"#, None),
+            Some("cargo-nextest JUnit")
+        );
+        assert_eq!(
+            detected("<TestRun/>", Some("trx")),
+            Some("Visual Studio TRX")
+        );
+        assert_eq!(
+            detected(r#"{"testResults":[]}"#, None),
+            Some("Jest/Vitest JSON")
+        );
+        assert_eq!(detected("# no plan yet\n", Some("tap")), Some("TAP"));
+        assert_eq!(detected("# no plan yet\n", None), None);
+        assert_eq!(detected("<html/>", None), None);
+    }
+
+    #[test]
+    fn patterns_of_formats() {
+        //when
+        let patterns = file_patterns();
+
+        //then
+        assert!(patterns.iter().any(|p| p.as_str() == "TEST*.xml"));
+        assert!(patterns.iter().any(|p| p.as_str() == "*.trx"));
+        assert!(FORMATS
+            .iter()
+            .all(|format| !format.file_patterns().is_empty()));
+    }
+}
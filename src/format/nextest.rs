@@ -0,0 +1,150 @@
+//! JUnit variant written by cargo-nextest. Every test binary is a `<testsuite>` named by its
+//! binary id (`crate`, `crate::bin/name`, `crate::test_file`), suites have no `time` and test
+//! case names are full test paths.
+
+use crate::duration::Duration;
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{optional_time, TestCase, TestSuite};
+use quick_xml::de::from_str;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde_derive::Deserialize;
+
+#[derive(Deserialize)]
+struct NextestRun {
+    #[serde(rename = "testsuite", default)]
+    test_suites: Vec<NextestSuite>,
+}
+
+#[derive(Deserialize)]
+struct NextestSuite {
+    #[serde(rename = "@name")]
+    name: String,
+
+    #[serde(rename = "@time", default, deserialize_with = "optional_time")]
+    time: Option<Duration>,
+
+    #[serde(rename = "@tests", default)]
+    tests: Option<usize>,
+
+    #[serde(rename = "@failures", default)]
+    failures: Option<usize>,
+
+    #[serde(rename = "@errors", default)]
+    errors: Option<usize>,
+
+    #[serde(rename = "testcase", default)]
+    test_cases: Vec<TestCase>,
+}
+
+/// Nextest writes `uuid` on root element, report name is configurable so it is not reliable
+pub fn is_nextest(content: &str) -> bool {
+    let mut reader = Reader::from_str(content);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                return e.local_name().as_ref() == b"testsuites"
+                    && e.attributes().flatten().any(|a| a.key.as_ref() == b"uuid");
+            }
+            Ok(Event::Eof) | Err(_) => return false,
+            _ => {}
+        }
+    }
+}
+
+/// Crate of binary id, `my-crate::bin/tool` belongs to `my-crate`
+fn crate_name(binary_id: &str) -> &str {
+    binary_id.split("::").next().unwrap()
+}
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    let run = from_str::<NextestRun>(content).map_err(|e| e.to_string())?;
+    Ok(run
+        .test_suites
+        .into_iter()
+        .map(|suite| TestSuite {
+            time: suite
+                .time
+                .unwrap_or_else(|| suite.test_cases.iter().map(|tc| tc.time).sum()),
+            parents: vec![crate_name(&suite.name).to_string()],
+            name: suite.name,
+            tests: suite.tests,
+            failures: suite.failures,
+            errors: suite.errors,
+            test_cases: suite.test_cases,
+            ..Default::default()
+        })
+        .collect())
+}
+
+pub struct Nextest;
+
+impl ReportFormat for Nextest {
+    fn name(&self) -> &'static str {
+        "cargo-nextest JUnit"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["junit.xml"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.as_deref() == Some("testsuites") && is_nextest(sniff.content)
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Outcome;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="nextest-run" tests="3" failures="1" errors="0" uuid="45c50d4c-6c3b-4b8a-9d2b-0dbb5d5e2d1a" timestamp="2024-10-18T20:40:34.000+02:00" time="1.5">
+    <testsuite name="my-crate" tests="2" disabled="0" errors="0" failures="1">
+        <testcase name="parser::tests::parses" classname="my-crate" timestamp="2024-10-18T20:40:34.000+02:00" time="0.25">
+        </testcase>
+        <testcase name="parser::tests::fails" classname="my-crate" timestamp="2024-10-18T20:40:34.000+02:00" time="0.75">
+            <failure type="test failure">thread panicked</failure>
+            <system-out>output</system-out>
+        </testcase>
+    </testsuite>
+    <testsuite name="my-crate::bin/tool" tests="1" disabled="0" errors="0" failures="0">
+        <testcase name="cli_works" classname="my-crate::bin/tool" timestamp="2024-10-18T20:40:34.000+02:00" time="0.5">
+        </testcase>
+    </testsuite>
+</testsuites>"#;
+
+    #[test]
+    fn detects_nextest() {
+        //then
+        assert!(is_nextest(REPORT));
+        assert!(!is_nextest("<testsuites name=\"nextest-run\"/>"));
+        assert!(!is_nextest("<testsuite uuid=\"x\"/>"));
+    }
+
+    #[test]
+    fn parses_binaries_as_suites() {
+        //when
+        let result = parse(REPORT).unwrap();
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "my-crate");
+        assert_eq!(result[0].time.as_secs_f64(), 1.0);
+        assert_eq!(result[0].parents, vec![String::from("my-crate")]);
+        assert_eq!(result[0].test_cases[0].name, "parser::tests::parses");
+        assert!(matches!(
+            result[0].test_cases[1].outcome,
+            Outcome::Failed(_)
+        ));
+        assert!(result[0].check_counters().is_empty());
+        assert_eq!(result[1].name, "my-crate::bin/tool");
+        assert_eq!(result[1].parents, vec![String::from("my-crate")]);
+        assert_eq!(result[1].time.as_secs_f64(), 0.5);
+    }
+}
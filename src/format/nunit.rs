@@ -0,0 +1,230 @@
+//! NUnit 3 `test-run` XML written by `nunit3-console` and `dotnet test --logger nunit`.
+//! Suites nest as assembly, namespaces and fixtures, fixtures become suites.
+
+use crate::duration::Duration;
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use quick_xml::de::from_str;
+use serde_derive::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct TestRun {
+    #[serde(rename = "test-suite", default)]
+    test_suites: Vec<NUnitSuite>,
+}
+
+#[derive(Deserialize)]
+struct NUnitSuite {
+    #[serde(rename = "@type", default)]
+    kind: String,
+    #[serde(rename = "@name", default)]
+    name: String,
+    #[serde(rename = "@fullname", default)]
+    full_name: String,
+    #[serde(rename = "@duration", default)]
+    duration: Option<Duration>,
+    #[serde(rename = "test-suite", default)]
+    test_suites: Vec<NUnitSuite>,
+    #[serde(rename = "test-case", default)]
+    test_cases: Vec<NUnitCase>,
+}
+
+#[derive(Deserialize)]
+struct NUnitCase {
+    #[serde(rename = "@name")]
+    name: String,
+    #[serde(rename = "@classname", default)]
+    class_name: String,
+    #[serde(rename = "@duration", default)]
+    duration: Duration,
+    #[serde(rename = "@result", default)]
+    result: String,
+    #[serde(rename = "@label", default)]
+    label: String,
+    #[serde(default)]
+    failure: Option<Message>,
+    #[serde(default)]
+    reason: Option<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(rename = "stack-trace", default)]
+    stack_trace: Option<String>,
+}
+
+impl From<NUnitCase> for TestCase {
+    fn from(case: NUnitCase) -> Self {
+        let details = |message: Option<Message>| OutcomeDetails {
+            message: message.as_ref().and_then(|m| m.message.clone()),
+            text: message.and_then(|m| m.stack_trace).unwrap_or_default(),
+            ..Default::default()
+        };
+        let outcome = match (case.result.as_str(), case.label.as_str()) {
+            ("Failed", "Error" | "Cancelled" | "Invalid") => {
+                Outcome::Errored(details(case.failure))
+            }
+            ("Failed", _) => Outcome::Failed(details(case.failure)),
+            ("Skipped" | "Inconclusive", _) => Outcome::Skipped(details(case.reason)),
+            _ => Outcome::Passed,
+        };
+        TestCase {
+            name: case.name,
+            time: case.duration,
+            classname: case.class_name,
+            outcome,
+        }
+    }
+}
+
+/// Test cases of fixture, including those in parameterized methods
+fn test_cases(suite: NUnitSuite) -> Vec<TestCase> {
+    let mut result: Vec<TestCase> = suite.test_cases.into_iter().map(TestCase::from).collect();
+    suite
+        .test_suites
+        .into_iter()
+        .for_each(|child| result.extend(test_cases(child)));
+    result
+}
+
+fn collect(suite: NUnitSuite, assembly: &str, result: &mut Vec<TestSuite>) {
+    let assembly = match suite.kind.as_str() {
+        "Assembly" => Path::new(&suite.name)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        _ => assembly.to_string(),
+    };
+    let parents: Vec<String> = Some(assembly.clone())
+        .into_iter()
+        .filter(|a| !a.is_empty())
+        .collect();
+    if suite.kind == "TestFixture" {
+        let name = suite.full_name.clone();
+        let duration = suite.duration;
+        let test_cases = test_cases(suite);
+        result.push(TestSuite {
+            name,
+            // Fixture duration includes its setup and teardown
+            time: duration.unwrap_or_else(|| test_cases.iter().map(|tc| tc.time).sum()),
+            test_cases,
+            parents,
+            ..Default::default()
+        });
+        return;
+    }
+    if !suite.test_cases.is_empty() {
+        let test_cases: Vec<TestCase> = suite.test_cases.into_iter().map(TestCase::from).collect();
+        result.push(TestSuite {
+            name: suite.full_name,
+            time: test_cases.iter().map(|tc| tc.time).sum(),
+            test_cases,
+            parents,
+            ..Default::default()
+        });
+    }
+    suite
+        .test_suites
+        .into_iter()
+        .for_each(|child| collect(child, &assembly, result));
+}
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    let run = from_str::<TestRun>(content).map_err(|e| e.to_string())?;
+    let mut result = Vec::new();
+    run.test_suites
+        .into_iter()
+        .for_each(|suite| collect(suite, "", &mut result));
+    Ok(result)
+}
+
+pub struct NUnit;
+
+impl ReportFormat for NUnit {
+    fn name(&self) -> &'static str {
+        "NUnit 3"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["TestResult.xml"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.as_deref() == Some("test-run")
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="utf-8" standalone="no"?>
+<test-run id="0" testcasecount="4" result="Failed" total="4" passed="2" failed="1" skipped="1" duration="3.5">
+  <command-line><![CDATA[nunit3-console Calc.Tests.dll]]></command-line>
+  <test-suite type="Assembly" id="0-1005" name="Calc.Tests.dll" fullname="/src/calc/bin/Debug/Calc.Tests.dll" result="Failed" duration="3.4">
+    <properties><property name="_PID" value="1234" /></properties>
+    <test-suite type="TestSuite" id="0-1006" name="Calc" fullname="Calc" result="Failed" duration="3.4">
+      <test-suite type="TestFixture" id="0-1000" name="CalcTests" fullname="Calc.CalcTests" classname="Calc.CalcTests" result="Failed" duration="3.25">
+        <test-case id="0-1001" name="Adds" fullname="Calc.CalcTests.Adds" methodname="Adds" classname="Calc.CalcTests" result="Passed" duration="1.0" />
+        <test-case id="0-1002" name="Divides" fullname="Calc.CalcTests.Divides" methodname="Divides" classname="Calc.CalcTests" result="Failed" duration="1.5">
+          <failure>
+            <message><![CDATA[Expected: 2 But was: 3]]></message>
+            <stack-trace><![CDATA[at Calc.CalcTests.Divides()]]></stack-trace>
+          </failure>
+        </test-case>
+        <test-suite type="ParameterizedMethod" id="0-1003" name="Multiplies" fullname="Calc.CalcTests.Multiplies" classname="Calc.CalcTests" result="Passed" duration="0.5">
+          <test-case id="0-1004" name="Multiplies(2,3)" fullname="Calc.CalcTests.Multiplies(2,3)" classname="Calc.CalcTests" result="Passed" duration="0.5" />
+        </test-suite>
+      </test-suite>
+      <test-suite type="TestFixture" id="0-1007" name="SlowTests" fullname="Calc.SlowTests" classname="Calc.SlowTests" result="Skipped" label="Ignored">
+        <test-case id="0-1008" name="Waits" fullname="Calc.SlowTests.Waits" classname="Calc.SlowTests" result="Skipped" label="Ignored" duration="0">
+          <reason><message><![CDATA[too slow]]></message></reason>
+        </test-case>
+      </test-suite>
+    </test-suite>
+  </test-suite>
+</test-run>"#;
+
+    #[test]
+    fn parses_fixtures_as_suites() {
+        //when
+        let result = parse(REPORT).unwrap();
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "Calc.CalcTests");
+        assert_eq!(result[0].time.as_secs_f64(), 3.25);
+        assert_eq!(result[0].parents, vec![String::from("Calc.Tests")]);
+        let names: Vec<&str> = result[0]
+            .test_cases
+            .iter()
+            .map(|tc| tc.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Adds", "Divides", "Multiplies(2,3)"]);
+        assert_eq!(
+            result[0].test_cases[1].outcome,
+            Outcome::Failed(OutcomeDetails {
+                message: Some(String::from("Expected: 2 But was: 3")),
+                kind: None,
+                text: String::from("at Calc.CalcTests.Divides()"),
+            })
+        );
+        assert_eq!(result[1].name, "Calc.SlowTests");
+        assert_eq!(result[1].time.as_secs_f64(), 0.0);
+        assert_eq!(
+            result[1].test_cases[0].outcome,
+            Outcome::Skipped(OutcomeDetails {
+                message: Some(String::from("too slow")),
+                ..Default::default()
+            })
+        );
+    }
+}
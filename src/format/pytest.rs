@@ -0,0 +1,176 @@
+//! JSON lines written by pytest `--report-log`. Every test has separate reports for setup, call
+//! and teardown, their durations are summed. Test files become suites.
+
+use crate::duration::Duration;
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Deserialize)]
+struct Report {
+    #[serde(rename = "$report_type")]
+    report_type: String,
+    #[serde(default)]
+    nodeid: String,
+    #[serde(default)]
+    when: String,
+    #[serde(default)]
+    outcome: String,
+    #[serde(default)]
+    duration: Duration,
+    #[serde(default)]
+    longrepr: Value,
+}
+
+pub fn is_report_log(content: &str) -> bool {
+    content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(|line| serde_json::from_str::<Value>(line).ok())
+        .is_some_and(|value| value.get("$report_type").is_some())
+}
+
+/// Text of failure, `longrepr` is a string or an object with `reprcrash`
+fn long_representation(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        Value::Array(items) => items.last().and_then(long_representation),
+        other => other
+            .pointer("/reprcrash/message")
+            .and_then(Value::as_str)
+            .map(String::from)
+            .or_else(|| Some(other.to_string())),
+    }
+}
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    // test file -> test name -> test case, ordered for stable output
+    let mut files: BTreeMap<String, BTreeMap<String, TestCase>> = BTreeMap::new();
+    for (number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let report = serde_json::from_str::<Report>(line)
+            .map_err(|e| format!("line {}: {}", number + 1, e))?;
+        if report.report_type != "TestReport" {
+            continue;
+        }
+        let (file, name) = report
+            .nodeid
+            .split_once("::")
+            .unwrap_or((report.nodeid.as_str(), ""));
+        let test_case = files
+            .entry(file.to_string())
+            .or_default()
+            .entry(name.to_string())
+            .or_insert_with(|| TestCase {
+                name: name.to_string(),
+                classname: file.to_string(),
+                ..Default::default()
+            });
+        test_case.time += report.duration;
+        let details = || OutcomeDetails {
+            message: long_representation(&report.longrepr),
+            ..Default::default()
+        };
+        // First problem wins, failure outside of test call is an error like in JUnit report
+        if test_case.outcome == Outcome::Passed {
+            test_case.outcome = match (report.outcome.as_str(), report.when.as_str()) {
+                ("failed", "call") => Outcome::Failed(details()),
+                ("failed", _) => Outcome::Errored(details()),
+                ("skipped", _) => Outcome::Skipped(details()),
+                _ => Outcome::Passed,
+            };
+        }
+    }
+    Ok(files
+        .into_iter()
+        .map(|(file, test_cases)| {
+            let test_cases: Vec<TestCase> = test_cases.into_values().collect();
+            TestSuite {
+                name: file,
+                time: test_cases.iter().map(|tc| tc.time).sum(),
+                test_cases,
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+
+pub struct Pytest;
+
+impl ReportFormat for Pytest {
+    fn name(&self) -> &'static str {
+        "pytest report log"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["report-log*.jsonl", "pytest*.jsonl"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.is_none() && is_report_log(sniff.content)
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"{"pytest_version": "8.3.3", "$report_type": "SessionStart"}
+{"nodeid": "tests/test_api.py", "outcome": "passed", "longrepr": null, "result": null, "sections": [], "$report_type": "CollectReport"}
+{"nodeid": "tests/test_api.py::test_get", "location": ["tests/test_api.py", 1, "test_get"], "keywords": {}, "outcome": "passed", "longrepr": null, "when": "setup", "user_properties": [], "sections": [], "duration": 0.5, "start": 1.0, "stop": 1.5, "$report_type": "TestReport"}
+{"nodeid": "tests/test_api.py::test_get", "location": ["tests/test_api.py", 1, "test_get"], "keywords": {}, "outcome": "passed", "longrepr": null, "when": "call", "user_properties": [], "sections": [], "duration": 1.25, "start": 1.5, "stop": 2.75, "$report_type": "TestReport"}
+{"nodeid": "tests/test_api.py::test_get", "location": ["tests/test_api.py", 1, "test_get"], "keywords": {}, "outcome": "passed", "longrepr": null, "when": "teardown", "user_properties": [], "sections": [], "duration": 0.25, "start": 2.75, "stop": 3.0, "$report_type": "TestReport"}
+{"nodeid": "tests/test_api.py::TestPost::test_post[json]", "location": ["tests/test_api.py", 9, "TestPost.test_post[json]"], "keywords": {}, "outcome": "failed", "longrepr": {"reprcrash": {"path": "tests/test_api.py", "lineno": 10, "message": "assert 1 == 2"}}, "when": "call", "user_properties": [], "sections": [], "duration": 1.0, "start": 3.0, "stop": 4.0, "$report_type": "TestReport"}
+{"nodeid": "tests/test_db.py::test_slow", "location": ["tests/test_db.py", 1, "test_slow"], "keywords": {}, "outcome": "skipped", "longrepr": ["tests/test_db.py", 1, "Skipped: slow"], "when": "setup", "user_properties": [], "sections": [], "duration": 0.0, "start": 4.0, "stop": 4.0, "$report_type": "TestReport"}
+{"exitstatus": 1, "$report_type": "SessionFinish"}
+"#;
+
+    #[test]
+    fn detects_report_log() {
+        //then
+        assert!(is_report_log(REPORT));
+        assert!(!is_report_log(
+            "{ \"type\": \"suite\", \"event\": \"started\" }"
+        ));
+    }
+
+    #[test]
+    fn parses_files_and_tests() {
+        //when
+        let result = parse(REPORT).unwrap();
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "tests/test_api.py");
+        assert_eq!(result[0].time.as_secs_f64(), 3.0);
+        let test_cases = &result[0].test_cases;
+        assert_eq!(test_cases[0].name, "TestPost::test_post[json]");
+        assert_eq!(test_cases[0].classname, "tests/test_api.py");
+        assert_eq!(
+            test_cases[0].outcome,
+            Outcome::Failed(OutcomeDetails {
+                message: Some(String::from("assert 1 == 2")),
+                ..Default::default()
+            })
+        );
+        assert_eq!(test_cases[1].name, "test_get");
+        assert_eq!(test_cases[1].time.as_secs_f64(), 2.0);
+        assert_eq!(
+            result[1].test_cases[0].outcome,
+            Outcome::Skipped(OutcomeDetails {
+                message: Some(String::from("Skipped: slow")),
+                ..Default::default()
+            })
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! TAP version 13 streams, as written by `prove`, `node --test` and `tap`. Durations come from
+//! `duration_ms` of YAML diagnostics. Top level test points with subtests (files of
+//! `node --test`) become suites, other top level points are test cases of one suite named by
+//! the parser after the report file.
+
+use crate::duration::Duration;
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use regex::Regex;
+
+/// Indentation of one level of subtests
+const INDENT: usize = 4;
+
+/// Stream starts with version, plan or numbered test point
+pub fn is_tap(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .is_some_and(|line| {
+            line.starts_with("TAP version")
+                || Regex::new(r"^(not )?ok( \d+\b|$)|^1\.\.\d+")
+                    .unwrap()
+                    .is_match(line)
+        })
+}
+
+/// Test point with its subtests
+struct Point {
+    test_case: TestCase,
+    subtests: Vec<TestCase>,
+}
+
+fn outcome(ok: bool, directive: Option<&str>, reason: &str) -> Outcome {
+    let details = || OutcomeDetails {
+        message: Some(reason.trim().to_string()).filter(|r| !r.is_empty()),
+        ..Default::default()
+    };
+    // TODO points run, so they keep their outcome and time
+    match directive.map(str::to_uppercase).as_deref() {
+        Some("SKIP") => Outcome::Skipped(details()),
+        _ if ok => Outcome::Passed,
+        _ => Outcome::Failed(OutcomeDetails::default()),
+    }
+}
+
+/// Value of `key: value` line of YAML block, quotes removed
+fn yaml_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let value = line.trim().strip_prefix(key)?.strip_prefix(':')?.trim();
+    Some(value.trim_matches(|c| c == '\'' || c == '"'))
+}
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    let point_regex = Regex::new(
+        r"^(not )?ok\b\s*(?:\d+)?\s*(?:- )?([^#]*?)\s*(?:#\s*(?i:(skip|todo))\S*\s*(.*))?$",
+    )
+    .unwrap();
+    // Points of every nesting level which did not get their parent yet
+    let mut levels: Vec<Vec<Point>> = vec![Vec::new()];
+    let mut in_yaml = false;
+    for (number, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        let depth = (line.len() - line.trim_start().len()) / INDENT;
+        if in_yaml {
+            if trimmed == "..." {
+                in_yaml = false;
+            } else if let Some(point) = levels.get_mut(depth).and_then(|l| l.last_mut()) {
+                let test_case = &mut point.test_case;
+                if let Some(value) = yaml_value(trimmed, "duration_ms") {
+                    test_case.time = value
+                        .parse::<f64>()
+                        .map_err(|e| e.to_string())
+                        .and_then(|milliseconds| Duration::try_from_secs_f64(milliseconds / 1000.0))
+                        .map_err(|e| format!("line {}: {}", number + 1, e))?;
+                } else if let Some(value) = yaml_value(trimmed, "message") {
+                    if let Outcome::Failed(details) = &mut test_case.outcome {
+                        details.message = Some(value.to_string());
+                    }
+                }
+            }
+            continue;
+        }
+        if trimmed == "---" {
+            in_yaml = true;
+            continue;
+        }
+        let Some(captures) = point_regex.captures(trimmed) else {
+            continue;
+        };
+        while levels.len() < depth + 2 {
+            levels.push(Vec::new());
+        }
+        let subtests = levels[depth + 1]
+            .drain(..)
+            .map(|point| point.test_case)
+            .collect();
+        levels.truncate(depth + 1);
+        levels[depth].push(Point {
+            test_case: TestCase {
+                name: captures[2].to_string(),
+                outcome: outcome(
+                    captures.get(1).is_none(),
+                    captures.get(3).map(|m| m.as_str()),
+                    captures.get(4).map_or("", |m| m.as_str()),
+                ),
+                ..Default::default()
+            },
+            subtests,
+        });
+    }
+    let mut result = Vec::new();
+    let mut loose = Vec::new();
+    for point in levels.into_iter().next().unwrap_or_default() {
+        if point.subtests.is_empty() {
+            loose.push(point.test_case);
+        } else {
+            let name = point.test_case.name;
+            result.push(TestSuite {
+                time: point.test_case.time,
+                test_cases: point
+                    .subtests
+                    .into_iter()
+                    .map(|tc| TestCase {
+                        classname: name.clone(),
+                        ..tc
+                    })
+                    .collect(),
+                name,
+                ..Default::default()
+            });
+        }
+    }
+    if !loose.is_empty() {
+        result.push(TestSuite {
+            time: loose.iter().map(|tc| tc.time).sum(),
+            test_cases: loose,
+            ..Default::default()
+        });
+    }
+    Ok(result)
+}
+
+pub struct Tap;
+
+impl ReportFormat for Tap {
+    fn name(&self) -> &'static str {
+        "TAP"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["*.tap"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.is_none() && (sniff.extension == Some("tap") || is_tap(sniff.content))
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_tap() {
+        //then
+        assert!(is_tap("TAP version 13\n1..1\nok 1 - works\n"));
+        assert!(is_tap("1..2\nok 1\nok 2\n"));
+        assert!(is_tap("not ok\n"));
+        assert!(!is_tap("invalid xml"));
+        assert!(!is_tap("okay, this is a log file\n"));
+        assert!(!is_tap("ok then\n"));
+    }
+
+    #[test]
+    fn parses_flat_stream() {
+        //given
+        let content = r#"TAP version 13
+1..5
+ok 1 - adds
+  ---
+  duration_ms: 1250.5
+  ...
+not ok 2 - divides
+  ---
+  duration_ms: 500
+  message: 'expected 2 to equal 3'
+  severity: fail
+  ...
+ok 3 - waits # SKIP too slow
+not ok 4 - rounds # TODO not implemented
+  ---
+  duration_ms: 250
+  ...
+ok 5 - parses # todo flaky
+"#;
+
+        //when
+        let result = parse(content).unwrap();
+
+        //then
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name, "");
+        assert_eq!(result[0].time.as_secs_f64(), 2.0005);
+        let test_cases = &result[0].test_cases;
+        assert_eq!(test_cases[0].name, "adds");
+        assert_eq!(test_cases[0].time.as_secs_f64(), 1.2505);
+        assert_eq!(
+            test_cases[1].outcome,
+            Outcome::Failed(OutcomeDetails {
+                message: Some(String::from("expected 2 to equal 3")),
+                ..Default::default()
+            })
+        );
+        assert_eq!(test_cases[2].name, "waits");
+        assert_eq!(
+            test_cases[2].outcome,
+            Outcome::Skipped(OutcomeDetails {
+                message: Some(String::from("too slow")),
+                ..Default::default()
+            })
+        );
+        assert!(test_cases[3].is_failed());
+        assert_eq!(test_cases[3].time.as_secs_f64(), 0.25);
+        assert_eq!(test_cases[4].outcome, Outcome::Passed);
+    }
+
+    #[test]
+    fn parses_subtests_as_suites() {
+        //given
+        let content = r#"TAP version 13
+# Subtest: test/calc.test.js
+    # Subtest: adds
+    ok 1 - adds
+      ---
+      duration_ms: 10
+      ...
+    # Subtest: nested
+        # Subtest: deep
+        ok 1 - deep
+        1..1
+    ok 2 - nested
+      ---
+      duration_ms: 20
+      ...
+    1..2
+ok 1 - test/calc.test.js
+  ---
+  duration_ms: 250
+  ...
+1..1
+# tests 3
+"#;
+
+        //when
+        let result = parse(content).unwrap();
+
+        //then
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name, "test/calc.test.js");
+        assert_eq!(result[0].time.as_secs_f64(), 0.25);
+        let names: Vec<&str> = result[0]
+            .test_cases
+            .iter()
+            .map(|tc| tc.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["adds", "nested"]);
+        assert_eq!(result[0].test_cases[1].time.as_secs_f64(), 0.02);
+        assert_eq!(result[0].test_cases[1].classname, "test/calc.test.js");
+    }
+}
@@ -0,0 +1,263 @@
+//! Visual Studio TRX written by `dotnet test --logger trx`. Results refer to test definitions
+//! by `testId`, definitions know class and assembly of test. Classes become suites.
+
+use crate::duration::Duration;
+use crate::error::FormatError;
+use crate::format::{ReportFormat, Sniff};
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use quick_xml::de::from_str;
+use serde_derive::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct TestRun {
+    #[serde(rename = "Results", default)]
+    results: Results,
+    #[serde(rename = "TestDefinitions", default)]
+    definitions: TestDefinitions,
+}
+
+#[derive(Deserialize, Default)]
+struct Results {
+    #[serde(rename = "UnitTestResult", default)]
+    results: Vec<UnitTestResult>,
+}
+
+#[derive(Deserialize)]
+struct UnitTestResult {
+    #[serde(rename = "@testId")]
+    test_id: String,
+    #[serde(rename = "@testName")]
+    test_name: String,
+    #[serde(rename = "@duration", default)]
+    duration: Option<String>,
+    #[serde(rename = "@outcome", default)]
+    outcome: String,
+    #[serde(rename = "Output", default)]
+    output: Option<Output>,
+}
+
+#[derive(Deserialize)]
+struct Output {
+    #[serde(rename = "ErrorInfo", default)]
+    error_info: Option<ErrorInfo>,
+}
+
+#[derive(Deserialize)]
+struct ErrorInfo {
+    #[serde(rename = "Message", default)]
+    message: Option<String>,
+    #[serde(rename = "StackTrace", default)]
+    stack_trace: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct TestDefinitions {
+    #[serde(rename = "UnitTest", default)]
+    unit_tests: Vec<UnitTest>,
+}
+
+#[derive(Deserialize)]
+struct UnitTest {
+    #[serde(rename = "@id")]
+    id: String,
+    #[serde(rename = "@storage", default)]
+    storage: String,
+    #[serde(rename = "TestMethod")]
+    method: TestMethod,
+}
+
+#[derive(Deserialize)]
+struct TestMethod {
+    #[serde(rename = "@className")]
+    class_name: String,
+}
+
+/// Duration written as `hh:mm:ss.fffffff`, hours can exceed 24. Values which do not fit are
+/// `None`, like malformed ones.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let mut parts = value.trim().splitn(3, ':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let whole = hours
+        .checked_mul(3600)?
+        .checked_add(minutes.checked_mul(60)?)?;
+    Duration::checked_from_secs(whole)?.checked_add(Duration::try_from_secs_f64(seconds).ok()?)
+}
+
+/// Assembly without directory and extension, `C:\src\Foo.Tests.dll` is `Foo.Tests`
+fn assembly_name(storage: &str) -> String {
+    let file = storage.rsplit(['/', '\\']).next().unwrap_or(storage);
+    Path::new(file)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn outcome(result: &UnitTestResult) -> Outcome {
+    let details = || {
+        let error = result.output.as_ref().and_then(|o| o.error_info.as_ref());
+        OutcomeDetails {
+            message: error.and_then(|e| e.message.clone()),
+            text: error
+                .and_then(|e| e.stack_trace.clone())
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    };
+    match result.outcome.as_str() {
+        "Failed" => Outcome::Failed(details()),
+        "Error" | "Aborted" | "Timeout" => Outcome::Errored(details()),
+        "NotExecuted" | "Inconclusive" | "NotRunnable" | "Disconnected" => {
+            Outcome::Skipped(details())
+        }
+        _ => Outcome::Passed,
+    }
+}
+
+pub fn parse(content: &str) -> Result<Vec<TestSuite>, String> {
+    let run = from_str::<TestRun>(content).map_err(|e| e.to_string())?;
+    let definitions: HashMap<&str, &UnitTest> = run
+        .definitions
+        .unit_tests
+        .iter()
+        .map(|unit_test| (unit_test.id.as_str(), unit_test))
+        .collect();
+    let mut suites: BTreeMap<(String, String), Vec<TestCase>> = BTreeMap::new();
+    for result in &run.results.results {
+        let (assembly, class) = match definitions.get(result.test_id.as_str()) {
+            Some(definition) => (
+                assembly_name(&definition.storage),
+                definition.method.class_name.clone(),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let time = match &result.duration {
+            Some(duration) => parse_duration(duration)
+                .ok_or_else(|| format!("invalid duration {} of {}", duration, result.test_name))?,
+            None => Duration::ZERO,
+        };
+        suites
+            .entry((assembly, class.clone()))
+            .or_default()
+            .push(TestCase {
+                name: result.test_name.clone(),
+                time,
+                classname: class,
+                outcome: outcome(result),
+            });
+    }
+    Ok(suites
+        .into_iter()
+        .map(|((assembly, class), test_cases)| TestSuite {
+            name: class,
+            time: test_cases.iter().map(|tc| tc.time).sum(),
+            test_cases,
+            parents: vec![assembly]
+                .into_iter()
+                .filter(|a| !a.is_empty())
+                .collect(),
+            ..Default::default()
+        })
+        .collect())
+}
+
+pub struct Trx;
+
+impl ReportFormat for Trx {
+    fn name(&self) -> &'static str {
+        "Visual Studio TRX"
+    }
+
+    fn file_patterns(&self) -> &'static [&'static str] {
+        &["*.trx"]
+    }
+
+    fn detect(&self, sniff: &Sniff) -> bool {
+        sniff.root.as_deref() == Some("TestRun")
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<TestSuite>, FormatError> {
+        Ok(parse(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<TestRun id="6b1e5f0e-6c36-4b6f-9d1d-5f3a2a5f6c11" name="build@agent 2024-10-18 20:40:34" xmlns="http://microsoft.com/schemas/VisualStudio/TeamTest/2010">
+  <Times creation="2024-10-18T20:40:34.0000000+02:00" start="2024-10-18T20:40:34.0000000+02:00" finish="2024-10-18T20:40:40.0000000+02:00" />
+  <Results>
+    <UnitTestResult executionId="e1" testId="t1" testName="Adds" computerName="agent" duration="00:00:01.5000000" startTime="2024-10-18T20:40:34.0000000+02:00" endTime="2024-10-18T20:40:35.5000000+02:00" testType="13cdc9d9-ddb5-4fa4-a97d-d965ccfc6d4b" outcome="Passed" testListId="l1" relativeResultsDirectory="e1" />
+    <UnitTestResult executionId="e2" testId="t2" testName="Divides" computerName="agent" duration="00:01:00.2500000" outcome="Failed" testListId="l1">
+      <Output>
+        <ErrorInfo>
+          <Message>Assert.Equal() Failure</Message>
+          <StackTrace>at Calc.Tests.CalcTests.Divides()</StackTrace>
+        </ErrorInfo>
+      </Output>
+    </UnitTestResult>
+    <UnitTestResult executionId="e3" testId="t3" testName="Parses" computerName="agent" outcome="NotExecuted" testListId="l1" />
+  </Results>
+  <TestDefinitions>
+    <UnitTest name="Adds" storage="c:\src\calc\bin\debug\calc.tests.dll" id="t1">
+      <Execution id="e1" />
+      <TestMethod codeBase="c:\src\calc\bin\debug\calc.tests.dll" adapterTypeName="executor://xunit/VsTestRunner2/netcoreapp" className="Calc.Tests.CalcTests" name="Adds" />
+    </UnitTest>
+    <UnitTest name="Divides" storage="c:\src\calc\bin\debug\calc.tests.dll" id="t2">
+      <Execution id="e2" />
+      <TestMethod codeBase="c:\src\calc\bin\debug\calc.tests.dll" adapterTypeName="executor://xunit/VsTestRunner2/netcoreapp" className="Calc.Tests.CalcTests" name="Divides" />
+    </UnitTest>
+    <UnitTest name="Parses" storage="/src/calc/bin/Debug/Calc.Parser.Tests.dll" id="t3">
+      <Execution id="e3" />
+      <TestMethod codeBase="/src/calc/bin/Debug/Calc.Parser.Tests.dll" adapterTypeName="executor://nunit3testexecutor/" className="Calc.Parser.Tests.ParserTests" name="Parses" />
+    </UnitTest>
+  </TestDefinitions>
+</TestRun>"#;
+
+    #[test]
+    fn parses_durations() {
+        //then
+        assert_eq!(
+            parse_duration("00:00:01.5000000"),
+            Some(Duration::from_secs_f64(1.5))
+        );
+        assert_eq!(
+            parse_duration("01:02:03.2500000"),
+            Some(Duration::from_secs_f64(3723.25))
+        );
+        assert_eq!(parse_duration("1.5"), None);
+        assert_eq!(parse_duration("18446744073709551615:00:00"), None);
+        assert_eq!(parse_duration("5124095576:00:00"), None);
+        assert_eq!(parse_duration("00:00:-1"), None);
+    }
+
+    #[test]
+    fn parses_classes_as_suites() {
+        //when
+        let result = parse(REPORT).unwrap();
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "Calc.Parser.Tests.ParserTests");
+        assert_eq!(result[0].parents, vec![String::from("Calc.Parser.Tests")]);
+        assert!(result[0].test_cases[0].is_skipped());
+        assert_eq!(result[1].name, "Calc.Tests.CalcTests");
+        assert_eq!(result[1].parents, vec![String::from("calc.tests")]);
+        assert_eq!(result[1].time.as_secs_f64(), 61.75);
+        assert_eq!(result[1].test_cases[0].name, "Adds");
+        assert_eq!(result[1].test_cases[0].classname, "Calc.Tests.CalcTests");
+        assert_eq!(
+            result[1].test_cases[1].outcome,
+            Outcome::Failed(OutcomeDetails {
+                message: Some(String::from("Assert.Equal() Failure")),
+                kind: None,
+                text: String::from("at Calc.Tests.CalcTests.Divides()"),
+            })
+        );
+    }
+}
@@ -1,54 +1,128 @@
+use crate::error::ReportError;
+use crate::format;
 use crate::model::FilePath;
-use std::collections::HashSet;
+use glob::{MatchOptions, Pattern};
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
 
-pub fn list_xml_files_in_dir(path: &String) -> Vec<FilePath> {
-    fs::read_dir(path)
-        .map(|entries| {
-            entries
-                .filter_map(|entry| {
-                    let entry = entry.ok()?;
-                    let path = entry.path();
-                    let is_xml = path
-                        .extension()
-                        .and_then(|ext| ext.to_str())
-                        .unwrap_or("")
-                        .eq_ignore_ascii_case("xml");
-                    let starts_with_test = path
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .unwrap_or("")
-                        .starts_with("TEST");
-                    if is_xml && starts_with_test {
-                        Some(FilePath {
-                            path: path.to_str()?.to_string(),
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .collect()
-        })
-        .unwrap_or_else(|_| {
-            eprintln!("Can't list files in directory {}", path);
-            Vec::new()
-        })
+#[derive(Debug)]
+pub struct LoaderOptions {
+    /// How deep to descend into directories, 1 means only files directly in given directory
+    pub max_depth: usize,
+    /// File has to match at least one of these patterns
+    pub include: Vec<Pattern>,
+    /// Files and directories matching any of these patterns are skipped
+    pub exclude: Vec<Pattern>,
+    pub follow_symlinks: bool,
 }
 
-pub fn list_xml_files_in_dirs(paths: Vec<String>) -> Vec<FilePath> {
-    let unique: Vec<String> = paths
-        .into_iter()
-        .collect::<HashSet<_>>()
+impl Default for LoaderOptions {
+    fn default() -> Self {
+        LoaderOptions {
+            max_depth: usize::MAX,
+            include: format::file_patterns(),
+            exclude: Vec::new(),
+            follow_symlinks: false,
+        }
+    }
+}
+
+/// Pattern without `/` is matched against file name only, otherwise against path relative
+/// to the directory where search started. Case is ignored, so `TEST-a.XML` is a report too.
+fn matches(pattern: &Pattern, relative: &Path) -> bool {
+    let options = MatchOptions {
+        case_sensitive: false,
+        ..MatchOptions::new()
+    };
+    if pattern.as_str().contains('/') {
+        pattern.matches_path_with(relative, options)
+    } else {
+        relative
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| pattern.matches_with(name, options))
+    }
+}
+
+pub fn list_xml_files_in_dir(
+    path: &String,
+    options: &LoaderOptions,
+) -> Vec<Result<FilePath, ReportError>> {
+    let root = Path::new(path);
+    if !root.is_dir() {
+        return vec![Err(ReportError::Search {
+            path: path.clone(),
+            message: String::from("not a directory"),
+        })];
+    }
+    let is_excluded = |entry_path: &Path| {
+        let relative = entry_path.strip_prefix(root).unwrap_or(entry_path);
+        options.exclude.iter().any(|p| matches(p, relative))
+    };
+    WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(options.max_depth)
+        .follow_links(options.follow_symlinks)
+        .sort_by_file_name()
         .into_iter()
-        .collect();
-    unique
-        .iter()
-        .map(|path| list_xml_files_in_dir(path))
-        .flatten()
+        .filter_entry(|entry| !is_excluded(entry.path()))
+        .filter_map(|entry| {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    return Some(Err(ReportError::Search {
+                        path: e
+                            .path()
+                            .map_or_else(|| path.clone(), |p| p.to_string_lossy().into_owned()),
+                        message: e.to_string(),
+                    }))
+                }
+            };
+            if !entry.file_type().is_file() {
+                return None;
+            }
+            let relative = entry.path().strip_prefix(root).ok()?;
+            if options.include.iter().any(|p| matches(p, relative)) {
+                Some(Ok(FilePath {
+                    path: entry.path().to_str()?.to_string(),
+                }))
+            } else {
+                None
+            }
+        })
         .collect()
 }
 
+/// Lists files from all directories, file reachable from more than one directory
+/// (by overlapping paths or symlinks) is returned only once. Directories which can not be
+/// searched are returned separately, the search goes on without them.
+pub fn list_xml_files_in_dirs(
+    paths: Vec<String>,
+    options: &LoaderOptions,
+) -> (Vec<FilePath>, Vec<ReportError>) {
+    let mut unique = BTreeMap::new();
+    let mut errors = Vec::new();
+    for result in paths
+        .iter()
+        .flat_map(|path| list_xml_files_in_dir(path, options))
+    {
+        let file = match result {
+            Ok(file) => file,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        let canonical = fs::canonicalize(&file.path).unwrap_or_else(|_| file.path.clone().into());
+        unique.entry(canonical).or_insert(file);
+    }
+    (unique.into_values().collect(), errors)
+}
+
 #[cfg(test)]
+#[allow(clippy::needless_borrows_for_generic_args)]
 mod tests {
     use super::*;
     use std::fs::File;
@@ -62,7 +136,10 @@ mod tests {
         File::create(&dir.path().join("TEST-b.xml")).unwrap();
 
         //when
-        let dirs = list_xml_files_in_dir(&dir.path().to_string_lossy().to_string());
+        let dirs = list_xml_files_in_dir(
+            &dir.path().to_string_lossy().to_string(),
+            &LoaderOptions::default(),
+        );
 
         //then
         assert_eq!(dirs.len(), 2)
@@ -76,7 +153,10 @@ mod tests {
         File::create(&dir.path().join("TEST-b.xml")).unwrap();
 
         //when
-        let dirs = list_xml_files_in_dir(&dir.path().to_string_lossy().to_string());
+        let dirs = list_xml_files_in_dir(
+            &dir.path().to_string_lossy().to_string(),
+            &LoaderOptions::default(),
+        );
 
         //then
         assert_eq!(dirs.len(), 1)
@@ -90,12 +170,50 @@ mod tests {
         File::create(&dir.path().join("TEST-b.xml")).unwrap();
 
         //when
-        let dirs = list_xml_files_in_dir(&dir.path().to_string_lossy().to_string());
+        let dirs = list_xml_files_in_dir(
+            &dir.path().to_string_lossy().to_string(),
+            &LoaderOptions::default(),
+        );
 
         //then
         assert_eq!(dirs.len(), 1)
     }
 
+    #[test]
+    fn test_list_xml_files_in_dir_ignores_case() {
+        //given
+        let dir = tempdir().unwrap();
+        File::create(&dir.path().join("TEST-a.XML")).unwrap();
+        File::create(&dir.path().join("test-b.xml")).unwrap();
+
+        //when
+        let dirs = list_xml_files_in_dir(
+            &dir.path().to_string_lossy().to_string(),
+            &LoaderOptions::default(),
+        );
+
+        //then
+        assert_eq!(dirs.len(), 2)
+    }
+
+    #[test]
+    fn test_list_xml_files_in_dir_includes_trx() {
+        //given
+        let dir = tempdir().unwrap();
+        File::create(&dir.path().join("build_2024-10-18.trx")).unwrap();
+        File::create(&dir.path().join("TEST-b.xml")).unwrap();
+        File::create(&dir.path().join("coverage.xml")).unwrap();
+
+        //when
+        let dirs = list_xml_files_in_dir(
+            &dir.path().to_string_lossy().to_string(),
+            &LoaderOptions::default(),
+        );
+
+        //then
+        assert_eq!(dirs.len(), 2)
+    }
+
     #[test]
     fn test_load_2_dirs() {
         //given
@@ -105,14 +223,144 @@ mod tests {
         File::create(&dir1.path().join("TEST-b.xml")).unwrap();
 
         //when
-        let dirs = list_xml_files_in_dirs(
+        let (dirs, errors) = list_xml_files_in_dirs(
             vec![
                 dir1.path().to_string_lossy().to_string(),
                 dir2.path().to_string_lossy().to_string(),
+            ],
+            &LoaderOptions::default(),
+        );
+
+        //then
+        assert_eq!(dirs.len(), 2);
+        assert!(errors.is_empty())
+    }
+
+    #[test]
+    fn test_list_xml_files_in_dir_recursive() {
+        //given
+        let dir = tempdir().unwrap();
+        let nested = dir.path().join("module/target/surefire-reports");
+        fs::create_dir_all(&nested).unwrap();
+        File::create(&dir.path().join("TEST-a.xml")).unwrap();
+        File::create(&nested.join("TEST-b.xml")).unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+
+        //when
+        let all = list_xml_files_in_dir(&root, &LoaderOptions::default());
+        let shallow = list_xml_files_in_dir(
+            &root,
+            &LoaderOptions {
+                max_depth: 1,
+                ..LoaderOptions::default()
+            },
+        );
+
+        //then
+        assert_eq!(all.len(), 2);
+        assert_eq!(shallow.len(), 1);
+    }
+
+    #[test]
+    fn test_list_xml_files_in_dir_include_exclude() {
+        //given
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/build/test-results/test")).unwrap();
+        fs::create_dir_all(dir.path().join("b/build/test-results/integration")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules")).unwrap();
+        File::create(&dir.path().join("a/build/test-results/test/report.xml")).unwrap();
+        File::create(&dir.path().join("b/build/test-results/integration/report.xml")).unwrap();
+        File::create(&dir.path().join("node_modules/report.xml")).unwrap();
+        File::create(&dir.path().join("report.xml")).unwrap();
+
+        //when
+        let dirs = list_xml_files_in_dir(
+            &dir.path().to_string_lossy().to_string(),
+            &LoaderOptions {
+                include: vec![Pattern::new("*.xml").unwrap()],
+                exclude: vec![
+                    Pattern::new("node_modules").unwrap(),
+                    Pattern::new("*/build/test-results/integration").unwrap(),
+                ],
+                ..LoaderOptions::default()
+            },
+        );
+
+        //then
+        let names: Vec<&Path> = dirs
+            .iter()
+            .map(|f| Path::new(&f.as_ref().unwrap().path))
+            .map(|path| path.strip_prefix(dir.path()).unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                Path::new("a/build/test-results/test/report.xml"),
+                Path::new("report.xml")
             ]
         );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_xml_files_in_dir_symlinks() {
+        //given
+        let dir = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        File::create(&target.path().join("TEST-a.xml")).unwrap();
+        std::os::unix::fs::symlink(target.path(), dir.path().join("link")).unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+
+        //when
+        let skipped = list_xml_files_in_dir(&root, &LoaderOptions::default());
+        let followed = list_xml_files_in_dir(
+            &root,
+            &LoaderOptions {
+                follow_symlinks: true,
+                ..LoaderOptions::default()
+            },
+        );
 
         //then
-        assert_eq!(dirs.len(), 2)
+        assert_eq!(skipped.len(), 0);
+        assert_eq!(followed.len(), 1);
+    }
+
+    #[test]
+    fn test_load_overlapping_dirs() {
+        //given
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("module")).unwrap();
+        File::create(&dir.path().join("module/TEST-a.xml")).unwrap();
+
+        //when
+        let (dirs, errors) = list_xml_files_in_dirs(
+            vec![
+                dir.path().to_string_lossy().to_string(),
+                dir.path().join("module").to_string_lossy().to_string(),
+                dir.path().join("module/../module").to_string_lossy().to_string(),
+            ],
+            &LoaderOptions::default(),
+        );
+
+        //then
+        assert_eq!(dirs.len(), 1);
+        assert!(errors.is_empty())
+    }
+
+    #[test]
+    fn test_list_xml_files_in_missing_dir() {
+        //when
+        let (dirs, errors) = list_xml_files_in_dirs(
+            vec!["non_existent_dir".to_string()],
+            &LoaderOptions::default(),
+        );
+
+        //then
+        assert!(dirs.is_empty());
+        assert_eq!(
+            errors[0].to_string(),
+            "non_existent_dir: can't search for reports: not a directory"
+        )
     }
 }
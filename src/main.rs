@@ -1,56 +1,445 @@
-use crate::model::TestSuite;
-use clap::Parser;
+use crate::error::ReportError;
+use crate::estimator::{Estimation, Estimator};
+use crate::loader::LoaderOptions;
+use crate::model::{FilePath, TestSuite};
+use crate::output::{Format, Shard};
+use crate::partitioner::Strategy;
+use crate::selector::{Runner, ShardSpec};
+use clap::{Parser, Subcommand, ValueEnum};
+use glob::Pattern;
+use processing::{Grouping, LetterPolicy};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+mod duration;
+mod error;
+mod estimator;
+mod format;
 mod loader;
 mod model;
+mod output;
 mod parser;
+mod partitioner;
 mod processing;
+mod selector;
+mod store;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum GroupBy {
+    FirstLetter,
+    Class,
+    Package,
+    Module,
+    Prefix,
+    Regex,
+    TestCase,
+}
 
 #[derive(Parser, Debug)]
-#[command(name = "command ...")]
+#[command(name = "command ...", args_conflicts_with_subcommands = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    plan: PlanArgs,
+
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Append timings from reports to timing store, as one run
+    #[command(visible_alias = "merge")]
+    Record {
+        /// Timing store, a file with one JSON record per suite and run
+        #[arg(long, default_value = DEFAULT_STORE)]
+        store: PathBuf,
+
+        /// Identifier of run, recording the same run again replaces its timings.
+        /// Defaults to timestamp.
+        #[arg(long)]
+        run_id: Option<String>,
+
+        /// Commit the tests ran on
+        #[arg(long)]
+        commit: Option<String>,
+
+        /// Time of run in seconds since Unix epoch, defaults to now
+        #[arg(long)]
+        timestamp: Option<u64>,
+
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Divide tests into groups by timings aggregated over runs in timing store
+    Split {
+        /// Timing store written by `record`
+        #[arg(long, default_value = DEFAULT_STORE)]
+        store: PathBuf,
+
+        /// How durations from many runs are combined
+        #[arg(short, long, value_enum, default_value_t = Estimator::default())]
+        estimator: Estimator,
+
+        /// Number of runs after which weight of a run halves, used with `--estimator ewma`
+        #[arg(long, default_value_t = Estimation::default().half_life, value_parser = positive)]
+        half_life: f64,
+
+        /// Ignore runs further from median than this many median absolute deviations,
+        /// so one slow run on a noisy agent does not move the estimate
+        #[arg(long, value_parser = positive)]
+        outlier_threshold: Option<f64>,
+
+        #[command(flatten)]
+        plan: PlanArgs,
+    },
+}
+
+const DEFAULT_STORE: &str = "timings.jsonl";
+
+fn positive(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if number > 0.0 && number.is_finite() => Ok(number),
+        Ok(_) => Err(String::from("must be a positive number")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// How groups are made and printed
+#[derive(clap::Args, Debug)]
+#[command(about = None, long_about = None)]
+struct PlanArgs {
     /// Number of groups
-    #[arg(short, long, default_value_t = 5)]
+    #[arg(short, long, default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..))]
     count: u16,
 
+    /// How tests are divided into groups
+    #[arg(short, long, value_enum, default_value_t = Strategy::default())]
+    strategy: Strategy,
+
+    /// What is distributed between groups
+    #[arg(short, long, value_enum, default_value_t = GroupBy::FirstLetter)]
+    group_by: GroupBy,
+
+    /// Keep lowercase first letters in their own buckets
+    #[arg(long)]
+    case_sensitive: bool,
+
+    /// Put accented letters into buckets of base letters and give other alphabets own buckets,
+    /// instead of the other bucket
+    #[arg(long)]
+    unicode_letters: bool,
+
+    /// Name of bucket for class names starting with digit, symbol or non ASCII letter
+    #[arg(long, default_value = "#")]
+    other_bucket: String,
+
+    /// Number of package segments used with `--group-by package`
+    #[arg(long, default_value_t = 1)]
+    package_depth: usize,
+
+    /// Number of characters used with `--group-by prefix`
+    #[arg(long, default_value_t = 2)]
+    prefix_length: usize,
+
+    /// Regex used with `--group-by regex`, first capture group (or whole match) becomes the key
+    #[arg(long, required_if_eq("group_by", "regex"))]
+    pattern: Option<Regex>,
+
+    /// How groups are printed
+    #[arg(short, long, value_enum, default_value_t = Format::default())]
+    format: Format,
+
+    /// Print only selector of one shard, given as `index/count` with index starting from 1.
+    /// Count of shards replaces `--count`.
+    #[arg(long)]
+    shard: Option<ShardSpec>,
+
+    /// Syntax of selectors in `--shard` mode and CI matrix formats
+    #[arg(short, long, value_enum, default_value_t = Runner::default())]
+    runner: Runner,
+
+    /// Directory for files written by `circleci`, `gradle-init`, `maven-includes` and `pytest-args`
+    /// formats
+    #[arg(long, default_value = ".")]
+    output_dir: PathBuf,
+}
+
+/// Where reports are searched for and how they are read
+#[derive(clap::Args, Debug)]
+#[command(about = None, long_about = None)]
+struct InputArgs {
+    /// How deep to search for reports in given paths
+    #[arg(long, default_value_t = usize::MAX, hide_default_value = true)]
+    max_depth: usize,
+
+    /// Glob pattern of report files, matched ignoring case against file name or, if it contains
+    /// `/`, against path relative to searched directory. Defaults to usual file names of all
+    /// known report formats.
+    #[arg(long)]
+    include: Vec<Pattern>,
+
+    /// Glob pattern of files and directories to skip
+    #[arg(long)]
+    exclude: Vec<Pattern>,
+
+    /// Follow symbolic links when searching for reports
+    #[arg(long)]
+    follow_symlinks: bool,
+
+    /// Number of threads parsing reports, defaults to number of CPUs
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+
+    /// Exit with error, before printing anything, when any path or report can not be read
+    #[arg(long)]
+    strict: bool,
+
     /// List of paths with JUNIT reports
     #[arg(default_value = ".")]
     paths: Vec<String>,
 }
 
+/// Suites to divide, with files they come from and inputs which could not be used
+struct Input {
+    files: Vec<FilePath>,
+    test_suites: Vec<TestSuite>,
+    skipped: Vec<ReportError>,
+}
+
 fn main() {
     let args = Args::parse();
+    match args.command {
+        None => plan(&args.plan, read_reports(args.input)),
+        Some(Command::Record {
+            store,
+            run_id,
+            commit,
+            timestamp,
+            input,
+        }) => {
+            let timestamp = timestamp.unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs())
+            });
+            let run = store::Run {
+                id: run_id.unwrap_or_else(|| timestamp.to_string()),
+                commit,
+                timestamp,
+            };
+            let input = read_reports(input);
+            if input.test_suites.is_empty() {
+                print_skipped(&input.skipped);
+                eprintln!(
+                    "No test suites found, nothing recorded to {}",
+                    store.display()
+                );
+                std::process::exit(1);
+            }
+            if let Err(e) = store::record(&store, &run, &input.test_suites) {
+                eprintln!("Can't write timings to {}: {}", store.display(), e);
+                std::process::exit(1);
+            }
+            eprintln!(
+                "Recorded {} suites of run {} to {}",
+                input.test_suites.len(),
+                run.id,
+                store.display()
+            );
+            print_skipped(&input.skipped);
+        }
+        Some(Command::Split {
+            store,
+            estimator,
+            half_life,
+            outlier_threshold,
+            plan: args,
+        }) => {
+            let (records, skipped) = match store::load(&store) {
+                Ok(loaded) => loaded,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            let input = Input {
+                files: vec![FilePath {
+                    path: store.to_string_lossy().into_owned(),
+                }],
+                test_suites: store::aggregate(
+                    records,
+                    &Estimation {
+                        estimator,
+                        half_life,
+                        outlier_threshold,
+                    },
+                ),
+                skipped,
+            };
+            plan(&args, input);
+        }
+    }
+}
+
+/// Parses reports found in given paths, exits in `--strict` mode when some input is skipped
+fn read_reports(args: InputArgs) -> Input {
+    let loader_options = LoaderOptions {
+        max_depth: args.max_depth,
+        include: match args.include.is_empty() {
+            true => format::file_patterns(),
+            false => args.include,
+        },
+        exclude: args.exclude,
+        follow_symlinks: args.follow_symlinks,
+    };
+    let (files, mut skipped) = loader::list_xml_files_in_dirs(args.paths, &loader_options);
+    let jobs = args
+        .jobs
+        .map(usize::from)
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |count| count.get()));
+    let reports = parser::files_to_report(&files, jobs);
+    let mut test_suites: Vec<TestSuite> = Vec::new();
+    let mut vec = Vec::new();
+    for (file, report) in files.into_iter().zip(reports) {
+        match report {
+            Ok(suites) => {
+                test_suites.extend(suites);
+                vec.push(file);
+            }
+            Err(e) => skipped.push(e),
+        }
+    }
+    if args.strict && !skipped.is_empty() {
+        print_skipped(&skipped);
+        std::process::exit(1);
+    }
+    test_suites
+        .iter()
+        .flat_map(|ts| ts.check_counters().into_iter().chain(ts.warnings.clone()))
+        .for_each(|warning| eprintln!("Warning: {}", warning));
+    Input {
+        files: vec,
+        test_suites,
+        skipped,
+    }
+}
 
-    let vec = loader::list_xml_files_in_dirs(args.paths);
-    let test_suites: Vec<TestSuite> = vec
+fn plan(args: &PlanArgs, input: Input) {
+    let Input {
+        files: vec,
+        test_suites,
+        skipped,
+    } = input;
+    let failed: Vec<String> = test_suites
         .iter()
-        .filter_map(|file_path| parser::file_to_report(file_path))
+        .flat_map(|ts| &ts.test_cases)
+        .filter(|tc| tc.is_failed())
+        .map(|tc| format!("{}.{}", tc.classname, tc.name))
         .collect();
-    let by_first_letter = processing::group_by_first_letter(test_suites);
-
-    let groups = processing::divide_into_groups(args.count, by_first_letter);
-
-    for group in &groups {
-        let string: String = group.iter().map(|tbl| tbl.letter.clone()).collect();
-        println!("=======================================");
-        println!(
-            "Group: {}: {}s",
-            string,
-            group.iter().map(|tbl| tbl.time).sum::<f32>().round()
-        );
-        group
-            .iter()
-            .for_each(|tbl| println!(" - {}: {}s", tbl.letter, tbl.time.round().abs()));
-    }
-    println!("=======================================");
-    println!(
-        "Total time: {}",
-        groups
-            .iter()
-            .flatten()
-            .map(|tbl| tbl.time)
-            .sum::<f32>()
-            .round()
-            .abs()
-    );
+    let grouping = match args.group_by {
+        GroupBy::FirstLetter => {
+            let policy = LetterPolicy {
+                fold_case: !args.case_sensitive,
+                unicode: args.unicode_letters,
+                other_bucket: args.other_bucket.clone(),
+            };
+            let unusual = processing::unusual_first_letters(&test_suites, &policy);
+            if !unusual.is_empty() {
+                eprintln!(
+                    "Warning: {} suites do not start with a letter and go to bucket {}: {}",
+                    unusual.len(),
+                    policy.other_bucket,
+                    unusual.join(", ")
+                );
+            }
+            Grouping::FirstLetter(policy)
+        }
+        GroupBy::Class => Grouping::Class,
+        GroupBy::Package => Grouping::Package(args.package_depth),
+        GroupBy::Module => Grouping::Module,
+        GroupBy::Prefix => Grouping::Prefix(args.prefix_length),
+        GroupBy::Regex => Grouping::Regex(args.pattern.clone().unwrap()),
+        GroupBy::TestCase => Grouping::TestCase,
+    };
+    let units = processing::group_by(&test_suites, &grouping);
+    let sources = processing::sources(&test_suites, &grouping);
+
+    let count = args.shard.map(|shard| shard.count).unwrap_or(args.count);
+    let groups = args.strategy.partitioner().partition(count, units);
+
+    if let Some(shard) = args.shard {
+        let members = &groups[shard.index as usize - 1];
+        let selector = selector::selector(args.runner, members, &sources);
+        if selector.is_empty() {
+            eprintln!(
+                "Warning: shard {}/{} has no tests",
+                shard.index, shard.count
+            );
+        }
+        println!("{}", selector);
+        print_skipped(&skipped);
+        return;
+    }
+
+    let mut shards = Shard::from_groups(groups, args.strategy == Strategy::Linear);
+    shards.iter_mut().for_each(|shard| {
+        shard.selector = selector::selector(args.runner, &shard.members, &sources);
+        shard.classes = selector::classes(&shard.members, &sources)
+            .into_iter()
+            .map(String::from)
+            .collect();
+        shard.node_ids = selector::pytest_node_ids(&shard.members, &sources)
+            .into_iter()
+            .collect();
+    });
+    match args.format {
+        Format::Text => print!("{}", output::text(&shards)),
+        Format::Json => println!("{}", output::json(&shards, &vec, &sources)),
+        Format::Csv => print!("{}", output::csv(&shards)),
+        Format::Github => println!("{}", output::github_matrix(&shards)),
+        Format::Gitlab => print!("{}", output::gitlab_matrix(&shards)),
+        Format::Circleci => print_files(
+            output::circleci_files(&shards, &args.output_dir),
+            &args.output_dir,
+        ),
+        Format::GradleInit => print_files(
+            output::gradle_init_files(&shards, &args.output_dir),
+            &args.output_dir,
+        ),
+        Format::MavenIncludes => print_files(
+            output::maven_files(&shards, &args.output_dir),
+            &args.output_dir,
+        ),
+        Format::PytestArgs => print_files(
+            output::pytest_files(&shards, &args.output_dir),
+            &args.output_dir,
+        ),
+    }
+    if !failed.is_empty() && args.format == Format::Text {
+        println!("Failed tests: {}", failed.len());
+        failed.iter().for_each(|name| println!(" - {}", name));
+    }
+    print_skipped(&skipped);
+}
+
+/// Summary of inputs which were not used, printed last so it is not lost among warnings
+fn print_skipped(skipped: &[ReportError]) {
+    if !skipped.is_empty() {
+        eprintln!("Skipped {} inputs:", skipped.len());
+        skipped.iter().for_each(|e| eprintln!(" - {}", e));
+    }
+}
+
+fn print_files(result: std::io::Result<Vec<PathBuf>>, dir: &Path) {
+    match result {
+        Ok(files) => files.iter().for_each(|f| println!("{}", f.display())),
+        Err(e) => {
+            eprintln!("Can't write files to {}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
+    }
 }
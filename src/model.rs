@@ -1,43 +1,371 @@
+use crate::duration::Duration;
+use serde::de::{Deserialize as _, Error};
+use serde::Deserializer;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
-#[serde(rename = "testsuite")]
+#[serde(rename = "testsuites")]
+pub struct TestSuites {
+    #[serde(rename = "@name", default)]
+    pub name: Option<String>,
+
+    #[serde(rename = "testsuite", default)]
+    pub test_suites: Vec<TestSuite>,
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename = "testsuite", from = "RawTestSuite")]
 pub struct TestSuite {
     #[serde(rename = "@name")]
     pub name: String,
 
     #[serde(rename = "@time")]
-    pub time: f32,
+    pub time: Duration,
+
+    #[serde(rename = "@tests", default)]
+    pub tests: Option<usize>,
+
+    #[serde(rename = "@failures", default)]
+    pub failures: Option<usize>,
 
-    #[serde(rename = "testcase")]
+    #[serde(rename = "@errors", default)]
+    pub errors: Option<usize>,
+
+    #[serde(rename = "@skipped", default)]
+    pub skipped: Option<usize>,
+
+    #[serde(rename = "testcase", default)]
     pub test_cases: Vec<TestCase>,
+
+    /// Nested suites, emptied when the report is flattened
+    #[serde(rename = "testsuite", default)]
+    pub test_suites: Vec<TestSuite>,
+
+    /// Names of enclosing suites, outermost first
+    #[serde(skip)]
+    pub parents: Vec<String>,
+
+    /// Report file the suite was read from
+    #[serde(skip)]
+    pub file: String,
+
+    /// Problems found in report which did not prevent reading the suite
+    #[serde(skip)]
+    pub warnings: Vec<String>,
 }
-#[derive(Debug, Deserialize, Serialize)]
-pub struct TestCase {
+
+/// Suite as written in report, turned into [TestSuite] after deserialization
+#[derive(Default, Deserialize)]
+pub struct RawTestSuite {
     #[serde(rename = "@name")]
     pub name: String,
 
-    #[serde(rename = "@time")]
-    pub time: f32,
+    #[serde(rename = "@time", default, deserialize_with = "optional_time")]
+    pub time: Option<Duration>,
 
-    #[serde(rename = "@classname")]
+    #[serde(rename = "@tests", default)]
+    pub tests: Option<usize>,
+
+    #[serde(rename = "@failures", default)]
+    pub failures: Option<usize>,
+
+    #[serde(rename = "@errors", default)]
+    pub errors: Option<usize>,
+
+    #[serde(rename = "@skipped", default)]
+    pub skipped: Option<usize>,
+
+    #[serde(rename = "testcase", default)]
+    pub test_cases: Vec<RawSuiteCase>,
+
+    #[serde(rename = "testsuite", default)]
+    pub test_suites: Vec<TestSuite>,
+}
+
+/// Test case of [RawTestSuite], remembers whether report gave its time
+#[derive(Deserialize)]
+#[serde(from = "RawTestCase")]
+pub struct RawSuiteCase {
+    pub test_case: TestCase,
+    pub timed: bool,
+}
+
+impl From<RawTestSuite> for TestSuite {
+    fn from(raw: RawTestSuite) -> Self {
+        let mut warnings = Vec::new();
+        let test_cases: Vec<TestCase> = raw
+            .test_cases
+            .into_iter()
+            .map(|case| {
+                if !case.timed {
+                    warnings.push(format!(
+                        "test case {} of suite {} has no time, using 0s",
+                        case.test_case.name, raw.name
+                    ));
+                }
+                case.test_case
+            })
+            .collect();
+        let time = raw.time.unwrap_or_else(|| {
+            let sum = test_cases.iter().map(|tc| tc.time).sum::<Duration>()
+                + raw.test_suites.iter().map(|ts| ts.time).sum();
+            warnings.push(format!(
+                "suite {} has no time, using sum of its test cases {}s",
+                raw.name,
+                sum.as_secs_f64()
+            ));
+            sum
+        });
+        TestSuite {
+            name: raw.name,
+            time,
+            tests: raw.tests,
+            failures: raw.failures,
+            errors: raw.errors,
+            skipped: raw.skipped,
+            test_cases,
+            test_suites: raw.test_suites,
+            warnings,
+            ..Default::default()
+        }
+    }
+}
+
+/// Parses time in seconds as written by various tools and locales: `1,234.5`, `12,5`,
+/// `1 234,5`, `12s`, `250ms`, `1.5min`. Single comma is a decimal separator, comma is read as
+/// thousands separator only when there are more of them or the number has a dot too.
+pub fn parse_time(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let units = [
+        ("ms", 0.001),
+        ("min", 60.0),
+        ("sec", 1.0),
+        ("s", 1.0),
+        ("m", 60.0),
+        ("h", 3600.0),
+    ];
+    let (number, factor) = units
+        .iter()
+        .find_map(|(unit, factor)| Some((value.strip_suffix(unit)?.trim_end(), *factor)))
+        .unwrap_or((value, 1.0));
+    let number: String = number
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '\u{a0}' | '\u{202f}' | '_' | '\''))
+        .collect();
+    let normalized = match (number.rfind('.'), number.rfind(',')) {
+        (Some(dot), Some(comma)) if dot > comma => number.replace(',', ""),
+        (Some(_), Some(_)) => number.replace('.', "").replace(',', "."),
+        (None, Some(_)) if number.matches(',').count() > 1 => number.replace(',', ""),
+        (None, Some(_)) => number.replace(',', "."),
+        _ => number,
+    };
+    normalized
+        .parse::<f64>()
+        .ok()
+        .and_then(|time| Duration::try_from_secs_f64(time * factor).ok())
+}
+
+fn lenient_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_time(&value).ok_or_else(|| D::Error::custom(format!("invalid time {}", value)))
+}
+
+pub fn optional_time<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    lenient_time(deserializer).map(Some)
+}
+
+impl TestSuite {
+    /// Turns suite with nested suites into flat list. Nested suites remember path to their parent,
+    /// container keeps only own test cases, so time is not counted twice.
+    pub fn flatten(mut self, parents: &[String]) -> Vec<TestSuite> {
+        self.parents = parents.to_vec();
+        let nested = std::mem::take(&mut self.test_suites);
+        if nested.is_empty() {
+            return vec![self];
+        }
+        let mut path = parents.to_vec();
+        path.push(self.name.clone());
+        let mut result = Vec::new();
+        if !self.test_cases.is_empty() {
+            // Counters and time of container cover nested suites too
+            self.time = self.test_cases.iter().map(|tc| tc.time).sum();
+            self.tests = None;
+            self.failures = None;
+            self.errors = None;
+            self.skipped = None;
+            result.push(self);
+        }
+        result.extend(nested.into_iter().flat_map(|ts| ts.flatten(&path)));
+        result
+    }
+
+    /// Compares counters from suite attributes with outcomes of test cases.
+    /// Returns description of every counter which does not match.
+    pub fn check_counters(&self) -> Vec<String> {
+        let count = |predicate: fn(&TestCase) -> bool| {
+            self.test_cases.iter().filter(|tc| predicate(tc)).count()
+        };
+        let actual = [
+            ("tests", self.tests, self.test_cases.len()),
+            (
+                "failures",
+                self.failures,
+                count(|tc| matches!(tc.outcome, Outcome::Failed(_))),
+            ),
+            (
+                "errors",
+                self.errors,
+                count(|tc| matches!(tc.outcome, Outcome::Errored(_))),
+            ),
+            ("skipped", self.skipped, count(TestCase::is_skipped)),
+        ];
+        actual
+            .iter()
+            .filter_map(|(counter, declared, counted)| match declared {
+                Some(declared) if declared != counted => Some(format!(
+                    "suite {} declares {}={} but contains {}",
+                    self.name, counter, declared, counted
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Time of suite without skipped test cases, used for balancing groups
+    pub fn time_without_skipped(&self) -> Duration {
+        let skipped: Duration = self
+            .test_cases
+            .iter()
+            .filter(|tc| tc.is_skipped())
+            .map(|tc| tc.time)
+            .sum();
+        self.time.saturating_sub(skipped)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(from = "RawTestCase")]
+pub struct TestCase {
+    pub name: String,
+    pub time: Duration,
     pub classname: String,
+    pub outcome: Outcome,
+}
+
+impl TestCase {
+    pub fn is_skipped(&self) -> bool {
+        matches!(self.outcome, Outcome::Skipped(_))
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.outcome, Outcome::Failed(_) | Outcome::Errored(_))
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum Outcome {
+    #[default]
+    Passed,
+    Failed(OutcomeDetails),
+    Errored(OutcomeDetails),
+    Skipped(OutcomeDetails),
+}
+
+impl Outcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Outcome::Passed => "passed",
+            Outcome::Failed(_) => "failed",
+            Outcome::Errored(_) => "errored",
+            Outcome::Skipped(_) => "skipped",
+        }
+    }
+}
+
+/// Content of `<failure>`, `<error>` or `<skipped>` element
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct OutcomeDetails {
+    #[serde(rename = "@message", default)]
+    pub message: Option<String>,
+
+    #[serde(rename = "@type", default)]
+    pub kind: Option<String>,
+
+    #[serde(rename = "$text", default)]
+    pub text: String,
+}
+
+/// Test case as written in report, turned into [TestCase] after deserialization
+#[derive(Deserialize)]
+struct RawTestCase {
+    #[serde(rename = "@name")]
+    name: String,
+
+    #[serde(rename = "@time", default, deserialize_with = "optional_time")]
+    time: Option<Duration>,
+
+    #[serde(rename = "@classname")]
+    classname: String,
+
+    #[serde(rename = "failure", default)]
+    failures: Vec<OutcomeDetails>,
+
+    #[serde(rename = "error", default)]
+    errors: Vec<OutcomeDetails>,
+
+    #[serde(rename = "skipped", default)]
+    skipped: Option<OutcomeDetails>,
+}
+
+impl From<RawTestCase> for TestCase {
+    fn from(raw: RawTestCase) -> Self {
+        let outcome = if let Some(error) = raw.errors.into_iter().next() {
+            Outcome::Errored(error)
+        } else if let Some(failure) = raw.failures.into_iter().next() {
+            Outcome::Failed(failure)
+        } else if let Some(skipped) = raw.skipped {
+            Outcome::Skipped(skipped)
+        } else {
+            Outcome::Passed
+        };
+        TestCase {
+            name: raw.name,
+            time: raw.time.unwrap_or_default(),
+            classname: raw.classname,
+            outcome,
+        }
+    }
+}
+
+impl From<RawTestCase> for RawSuiteCase {
+    fn from(raw: RawTestCase) -> Self {
+        RawSuiteCase {
+            timed: raw.time.is_some(),
+            test_case: TestCase::from(raw),
+        }
+    }
 }
 
 #[derive(Debug, Clone)] //PartialEq
 pub struct TimeByLetter {
-    pub time: f32,
-    pub letter: char,
+    pub time: Duration,
+    /// Grouping key, first letter of class name unless other grouping was chosen
+    pub key: String,
 }
 impl TimeByLetter {
-    pub fn new(time: f32, letter: char) -> Self {
-        TimeByLetter { time, letter }
+    pub fn new(time: impl Into<Duration>, key: impl Into<String>) -> Self {
+        TimeByLetter {
+            time: time.into(),
+            key: key.into(),
+        }
     }
 }
 
 impl PartialEq for TimeByLetter {
     fn eq(&self, other: &Self) -> bool {
-        self.letter == other.letter && self.time == other.time
+        self.key == other.key && self.time == other.time
     }
 }
 
@@ -48,6 +376,38 @@ pub struct FilePath {
 
 // impl PartialOrd for TimeByLetter {
 //     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-//         Some(self.letter.cmp(&other.letter))
+//         Some(self.key.cmp(&other.key))
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_lenient_times() {
+        //given
+        let secs = |value| parse_time(value).map(Duration::as_secs_f64);
+
+        //then
+        assert_eq!(secs("39.218"), Some(39.218));
+        assert_eq!(secs(" 12 "), Some(12.0));
+        assert_eq!(secs("1,234.5"), Some(1234.5));
+        assert_eq!(secs("1.234,5"), Some(1234.5));
+        assert_eq!(secs("12,5"), Some(12.5));
+        assert_eq!(secs("0,125"), Some(0.125));
+        assert_eq!(secs("12,345"), Some(12.345));
+        assert_eq!(secs("1,234"), Some(1.234));
+        assert_eq!(secs("1,234,567"), Some(1234567.0));
+        assert_eq!(secs("1 234,5"), Some(1234.5));
+        assert_eq!(secs("12s"), Some(12.0));
+        assert_eq!(secs("250 ms"), Some(0.25));
+        assert_eq!(secs("1.5min"), Some(90.0));
+        assert_eq!(secs("2h"), Some(7200.0));
+        assert_eq!(secs(""), None);
+        assert_eq!(secs("soon"), None);
+        assert_eq!(secs("NaN"), None);
+        assert_eq!(secs("-1"), None);
+        assert_eq!(secs("1e300"), None);
+    }
+}
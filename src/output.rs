@@ -0,0 +1,693 @@
+use crate::duration::Duration;
+use crate::model::{FilePath, TimeByLetter};
+use crate::partitioner::group_time;
+use crate::processing::Source;
+use crate::selector;
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// Human readable summary
+    #[default]
+    Text,
+    /// Groups with their sources and statistics, see [JSON_SCHEMA_VERSION]
+    Json,
+    /// One row per group member
+    Csv,
+    /// GitHub Actions `strategy.matrix` JSON object
+    Github,
+    /// GitLab `parallel:matrix` YAML fragment
+    Gitlab,
+    /// One file with test classes or files per node, like `circleci tests split` produces
+    Circleci,
+    /// One Gradle init script per group, restricting tests to classes of group
+    GradleInit,
+    /// Surefire/Failsafe `includesFile` per group and shared `excludesFile` with all known classes
+    MavenIncludes,
+    /// pytest argument files per group, with node ids to run and with node ids to deselect
+    PytestArgs,
+}
+
+/// Group with everything CI job needs to run it
+#[derive(Debug, PartialEq)]
+pub struct Shard {
+    /// Starts from 1, the same as index of `--shard`
+    pub index: usize,
+    pub duration: Duration,
+    /// Human readable name of group
+    pub label: String,
+    /// Members of group in syntax of chosen runner
+    pub selector: String,
+    pub members: Vec<TimeByLetter>,
+    /// Test classes of members, filled when known
+    pub classes: Vec<String>,
+    /// pytest node ids of members, filled when known
+    pub node_ids: Vec<String>,
+}
+
+impl Shard {
+    /// Labels are ranges of keys when `ranges` is set, which suits ranges from `--strategy linear`
+    pub fn from_groups(groups: Vec<Vec<TimeByLetter>>, ranges: bool) -> Vec<Shard> {
+        groups
+            .into_iter()
+            .enumerate()
+            .map(|(index, members)| Shard {
+                index: index + 1,
+                duration: group_time(&members),
+                label: label(&members, ranges),
+                selector: members
+                    .iter()
+                    .map(|tbl| tbl.key.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                members,
+                classes: Vec::new(),
+                node_ids: Vec::new(),
+            })
+            .collect()
+    }
+}
+
+/// Range `first..last`, letters glued together or keys separated by comma
+fn label(group: &[TimeByLetter], range: bool) -> String {
+    match (group.first(), group.last()) {
+        (Some(first), Some(last)) if range && group.len() > 1 => {
+            format!("{}..{}", first.key, last.key)
+        }
+        _ if group.iter().all(|tbl| tbl.key.chars().count() == 1) => {
+            group.iter().map(|tbl| tbl.key.as_str()).collect()
+        }
+        _ => group
+            .iter()
+            .map(|tbl| tbl.key.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+pub fn text(shards: &[Shard]) -> String {
+    let mut result = String::new();
+    for shard in shards {
+        result.push_str("=======================================\n");
+        result.push_str(&format!("Group: {}: {}\n", shard.label, shard.duration));
+        shard
+            .members
+            .iter()
+            .for_each(|tbl| result.push_str(&format!(" - {}: {}\n", tbl.key, tbl.time)));
+    }
+    result.push_str("=======================================\n");
+    result.push_str(&format!(
+        "Total time: {}\n",
+        shards.iter().map(|s| s.duration).sum::<Duration>()
+    ));
+    result
+}
+
+/// Version of document written by [json], raised on every incompatible change
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Longest group divided by the ideal duration, 1.0 means perfectly balanced groups
+pub fn imbalance(shards: &[Shard]) -> f64 {
+    let target = target_duration(shards);
+    let longest = shards.iter().map(|s| s.duration).max().unwrap_or_default();
+    if target > Duration::ZERO {
+        longest.ratio(target)
+    } else {
+        1.0
+    }
+}
+
+fn target_duration(shards: &[Shard]) -> Duration {
+    let total: Duration = shards.iter().map(|s| s.duration).sum();
+    total / shards.len().max(1) as u32
+}
+
+pub fn json(
+    shards: &[Shard],
+    files: &[FilePath],
+    sources: &BTreeMap<String, Vec<Source>>,
+) -> String {
+    let source_json = |source: &Source| {
+        let test_cases: Vec<Value> = source
+            .test_cases
+            .iter()
+            .map(|tc| {
+                json!({
+                    "name": tc.name,
+                    "classname": tc.classname,
+                    "duration": tc.time,
+                    "outcome": tc.outcome.name(),
+                })
+            })
+            .collect();
+        json!({
+            "suite": source.suite.name,
+            "file": source.suite.file,
+            "duration": source.suite.time,
+            "test_cases": test_cases,
+        })
+    };
+    let groups: Vec<Value> = shards
+        .iter()
+        .map(|shard| {
+            let members: Vec<Value> = shard
+                .members
+                .iter()
+                .map(|tbl| {
+                    let member_sources: Vec<Value> = sources
+                        .get(&tbl.key)
+                        .map(|s| s.iter().map(source_json).collect())
+                        .unwrap_or_default();
+                    json!({
+                        "key": tbl.key,
+                        "duration": tbl.time,
+                        "sources": member_sources,
+                    })
+                })
+                .collect();
+            json!({
+                "index": shard.index,
+                "label": shard.label,
+                "duration": shard.duration,
+                "selector": shard.selector,
+                "members": members,
+            })
+        })
+        .collect();
+    let document = json!({
+        "version": JSON_SCHEMA_VERSION,
+        "total_duration": shards.iter().map(|s| s.duration).sum::<Duration>(),
+        "target_duration": target_duration(shards),
+        "imbalance": imbalance(shards),
+        "files": files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>(),
+        "groups": groups,
+    });
+    serde_json::to_string_pretty(&document).unwrap()
+}
+
+/// Quotes field if it contains separator, quote or new line
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn csv(shards: &[Shard]) -> String {
+    let mut result = String::from("group,group_duration,member,duration\n");
+    for shard in shards {
+        for tbl in &shard.members {
+            result.push_str(&format!(
+                "{},{},{},{}\n",
+                shard.index,
+                shard.duration.as_secs_f64(),
+                csv_field(&tbl.key),
+                tbl.time.as_secs_f64()
+            ));
+        }
+    }
+    result
+}
+
+/// Object for `strategy.matrix`, used with `fromJSON` in workflow
+pub fn github_matrix(shards: &[Shard]) -> String {
+    let include: Vec<_> = shards
+        .iter()
+        .map(|shard| {
+            json!({
+                "index": shard.index,
+                "duration": shard.duration,
+                "selector": shard.selector,
+            })
+        })
+        .collect();
+    json!({ "include": include }).to_string()
+}
+
+/// Fragment to paste (or include) into job definition. GitLab accepts only strings as matrix
+/// values, they are written as JSON strings which are valid YAML.
+pub fn gitlab_matrix(shards: &[Shard]) -> String {
+    let mut result = String::from("parallel:\n  matrix:\n");
+    for shard in shards {
+        result.push_str(&format!(
+            "    - SHARD_INDEX: {}\n      SHARD_DURATION: {}\n      SHARD_SELECTOR: {}\n",
+            json!(shard.index.to_string()),
+            json!(shard.duration.round_secs().to_string()),
+            json!(shard.selector)
+        ));
+    }
+    result
+}
+
+/// Writes one file per shard, returns paths of written files
+fn write_files(
+    shards: &[Shard],
+    dir: &Path,
+    file_name: impl Fn(&Shard) -> String,
+    content: impl Fn(&Shard) -> String,
+) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    shards
+        .iter()
+        .map(|shard| {
+            let path = dir.join(file_name(shard));
+            fs::write(&path, content(shard))?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Writes `node-<index>.txt` with one test class or file per line for every shard, grouping
+/// keys like letters mean nothing to `circleci tests run`
+pub fn circleci_files(shards: &[Shard], dir: &Path) -> io::Result<Vec<PathBuf>> {
+    write_files(
+        shards,
+        dir,
+        |shard| format!("node-{}.txt", shard.index),
+        |shard| {
+            shard
+                .classes
+                .iter()
+                .map(|class| format!("{}\n", class))
+                .collect()
+        },
+    )
+}
+
+/// Writes `shard-<index>.init.gradle` for every shard
+pub fn gradle_init_files(shards: &[Shard], dir: &Path) -> io::Result<Vec<PathBuf>> {
+    write_files(
+        shards,
+        dir,
+        |shard| format!("shard-{}.init.gradle", shard.index),
+        |shard| selector::gradle_init_script(shard.classes.iter().map(String::as_str)),
+    )
+}
+
+/// Name of file with every known class, used by catch-all job running classes without history
+pub const MAVEN_CATCH_ALL: &str = "catch-all.excludes";
+
+/// Writes `shard-<index>.includes` for every shard, for `-Dsurefire.includesFile`, and
+/// [MAVEN_CATCH_ALL] for `-Dsurefire.excludesFile` of one more job, so new test classes which
+/// are not in any report still run somewhere
+pub fn maven_files(shards: &[Shard], dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let patterns = |classes: &mut dyn Iterator<Item = &String>| -> String {
+        classes
+            .map(|class| selector::maven_pattern(class))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|pattern| format!("{}\n", pattern))
+            .collect()
+    };
+    let mut files = write_files(
+        shards,
+        dir,
+        |shard| format!("shard-{}.includes", shard.index),
+        |shard| match shard.classes.is_empty() {
+            // Surefire falls back to default includes for empty file
+            true => format!("{}\n", selector::maven_pattern(selector::NO_TESTS_CLASS)),
+            false => patterns(&mut shard.classes.iter()),
+        },
+    )?;
+    let catch_all = dir.join(MAVEN_CATCH_ALL);
+    fs::write(
+        &catch_all,
+        patterns(&mut shards.iter().flat_map(|s| &s.classes)),
+    )?;
+    files.push(catch_all);
+    Ok(files)
+}
+
+/// Writes `shard-<index>.args` with node ids of shard, for `pytest @shard-<index>.args`, and
+/// `shard-<index>.deselect` deselecting node ids of all other shards, so tests without history
+/// run in every shard instead of none
+pub fn pytest_files(shards: &[Shard], dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = write_files(
+        shards,
+        dir,
+        |shard| format!("shard-{}.args", shard.index),
+        |shard| match shard.node_ids.is_empty() {
+            // pytest without arguments collects every test, and collecting none fails with exit
+            // code 5. Printing version runs nothing and succeeds.
+            true => String::from("--version\n"),
+            false => shard
+                .node_ids
+                .iter()
+                .map(|id| format!("{}\n", id))
+                .collect(),
+        },
+    )?;
+    files.extend(write_files(
+        shards,
+        dir,
+        |shard| format!("shard-{}.deselect", shard.index),
+        |shard| {
+            shards
+                .iter()
+                .filter(|other| other.index != shard.index)
+                .flat_map(|other| &other.node_ids)
+                .map(|id| format!("--deselect={}\n", id))
+                .collect()
+        },
+    )?);
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{TestCase, TestSuite};
+    use crate::processing::{self, Grouping, LetterPolicy};
+    use crate::selector::ShardSpec;
+    use tempfile::tempdir;
+
+    fn shards() -> Vec<Shard> {
+        Shard::from_groups(
+            vec![
+                vec![
+                    TimeByLetter::new(10.0, 'A'),
+                    TimeByLetter::new(2.5, 'B'),
+                    TimeByLetter::new(1.0, 'C'),
+                ],
+                vec![TimeByLetter::new(14.0, "D\"x")],
+            ],
+            true,
+        )
+    }
+
+    #[test]
+    fn shards_from_groups() {
+        //when
+        let shards = shards();
+
+        //then
+        assert_eq!(shards[0].index, 1);
+        assert_eq!(shards[0].duration, Duration::from_secs_f64(13.5));
+        assert_eq!(shards[0].label, "A..C");
+        assert_eq!(shards[0].selector, "A B C");
+        assert_eq!(shards[1].index, 2);
+        assert_eq!(shards[1].label, "D\"x");
+    }
+
+    #[test]
+    fn labels_without_ranges() {
+        //given
+        let groups = vec![
+            vec![TimeByLetter::new(10.0, 'A'), TimeByLetter::new(1.0, 'C')],
+            vec![TimeByLetter::new(1.0, "com"), TimeByLetter::new(1.0, "org")],
+        ];
+
+        //when
+        let shards = Shard::from_groups(groups, false);
+
+        //then
+        assert_eq!(shards[0].label, "AC");
+        assert_eq!(shards[1].label, "com, org");
+        assert!(text(&shards).contains("Group: AC: 11s\n"));
+    }
+
+    #[test]
+    fn shard_index_selects_the_same_group() {
+        //given
+        let groups = vec![
+            vec![TimeByLetter::new(1.0, 'A')],
+            vec![TimeByLetter::new(2.0, 'B')],
+            vec![TimeByLetter::new(3.0, 'C')],
+        ];
+
+        //when
+        let shards = Shard::from_groups(groups.clone(), false);
+
+        //then
+        for shard in &shards {
+            let spec: ShardSpec = format!("{}/{}", shard.index, shards.len()).parse().unwrap();
+            assert_eq!(groups[spec.index as usize - 1], shard.members);
+        }
+    }
+
+    #[test]
+    fn text_output() {
+        //when
+        let result = text(&shards()[..1]);
+
+        //then
+        assert_eq!(
+            result,
+            "=======================================\n\
+             Group: A..C: 14s\n \
+             - A: 10s\n \
+             - B: 3s\n \
+             - C: 1s\n\
+             =======================================\n\
+             Total time: 14s\n"
+        );
+    }
+
+    #[test]
+    fn github_matrix_output() {
+        //when
+        let result = github_matrix(&shards());
+
+        //then
+        assert_eq!(
+            result,
+            r#"{"include":[{"duration":13.5,"index":1,"selector":"A B C"},{"duration":14.0,"index":2,"selector":"D\"x"}]}"#
+        );
+    }
+
+    #[test]
+    fn gitlab_matrix_output() {
+        //when
+        let result = gitlab_matrix(&shards());
+
+        //then
+        assert_eq!(
+            result,
+            r#"parallel:
+  matrix:
+    - SHARD_INDEX: "1"
+      SHARD_DURATION: "14"
+      SHARD_SELECTOR: "A B C"
+    - SHARD_INDEX: "2"
+      SHARD_DURATION: "14"
+      SHARD_SELECTOR: "D\"x"
+"#
+        );
+    }
+
+    #[test]
+    fn circleci_files_output() {
+        //given
+        let dir = tempdir().unwrap();
+        let mut shards = shards();
+        shards[0].classes = vec![
+            String::from("com.foo.ATest"),
+            String::from("src/calc.test.js"),
+        ];
+
+        //when
+        let files = circleci_files(&shards, dir.path()).unwrap();
+
+        //then
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&files[0]).unwrap(),
+            "com.foo.ATest\nsrc/calc.test.js\n"
+        );
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "");
+    }
+
+    #[test]
+    fn json_output() {
+        //given
+        let suite = TestSuite {
+            name: String::from("com.foo.ATest"),
+            time: Duration::from_secs(10),
+            file: String::from("TEST-com.foo.ATest.xml"),
+            test_cases: vec![TestCase {
+                name: String::from("works"),
+                classname: String::from("com.foo.ATest"),
+                time: Duration::from_secs(10),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let suites = vec![suite];
+        let grouping = Grouping::FirstLetter(LetterPolicy::default());
+        let sources = processing::sources(&suites, &grouping);
+        let files = vec![FilePath {
+            path: String::from("TEST-com.foo.ATest.xml"),
+        }];
+        let shards = Shard::from_groups(vec![vec![TimeByLetter::new(10.0, 'A')], vec![]], true);
+
+        //when
+        let result: Value = serde_json::from_str(&json(&shards, &files, &sources)).unwrap();
+
+        //then
+        assert_eq!(
+            result,
+            json!({
+                "version": 1,
+                "total_duration": 10.0,
+                "target_duration": 5.0,
+                "imbalance": 2.0,
+                "files": ["TEST-com.foo.ATest.xml"],
+                "groups": [
+                    {
+                        "index": 1,
+                        "label": "A",
+                        "duration": 10.0,
+                        "selector": "A",
+                        "members": [{
+                            "key": "A",
+                            "duration": 10.0,
+                            "sources": [{
+                                "suite": "com.foo.ATest",
+                                "file": "TEST-com.foo.ATest.xml",
+                                "duration": 10.0,
+                                "test_cases": [{
+                                    "name": "works",
+                                    "classname": "com.foo.ATest",
+                                    "duration": 10.0,
+                                    "outcome": "passed",
+                                }],
+                            }],
+                        }],
+                    },
+                    {
+                        "index": 2,
+                        "label": "",
+                        "duration": 0.0,
+                        "selector": "",
+                        "members": [],
+                    },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn csv_output() {
+        //when
+        let result = csv(&shards());
+
+        //then
+        assert_eq!(
+            result,
+            "group,group_duration,member,duration\n\
+             1,13.5,A,10\n\
+             1,13.5,B,2.5\n\
+             1,13.5,C,1\n\
+             2,14,\"D\"\"x\",14\n"
+        );
+    }
+
+    #[test]
+    fn imbalance_of_shards() {
+        //then
+        assert_eq!(imbalance(&shards()), 14.0 / 13.75);
+        assert_eq!(imbalance(&[]), 1.0);
+    }
+
+    #[test]
+    fn gradle_init_files_output() {
+        //given
+        let dir = tempdir().unwrap();
+        let mut shards = shards();
+        shards[0].classes = vec![String::from("com.foo.ATest")];
+
+        //when
+        let files = gradle_init_files(&shards, dir.path()).unwrap();
+
+        //then
+        assert_eq!(files.len(), 2);
+        assert!(files[0].ends_with("shard-1.init.gradle"));
+        assert!(fs::read_to_string(&files[0])
+            .unwrap()
+            .contains("includeTestsMatching 'com.foo.ATest'"));
+        assert!(fs::read_to_string(&files[1])
+            .unwrap()
+            .contains("includeTestsMatching 'no.tests.in.this.Shard'"));
+    }
+
+    #[test]
+    fn maven_files_output() {
+        //given
+        let dir = tempdir().unwrap();
+        let mut shards = shards();
+        shards[0].classes = vec![
+            String::from("com.foo.BTest"),
+            String::from("com.foo.ATest$1"),
+            String::from("com.foo.ATest"),
+        ];
+        shards[1].classes = vec![String::from("DTest")];
+
+        //when
+        let files = maven_files(&shards, dir.path()).unwrap();
+
+        //then
+        assert_eq!(files.len(), 3);
+        assert!(files[0].ends_with("shard-1.includes"));
+        assert_eq!(
+            fs::read_to_string(&files[0]).unwrap(),
+            "com/foo/ATest.java\ncom/foo/BTest.java\n"
+        );
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "DTest.java\n");
+        assert!(files[2].ends_with(MAVEN_CATCH_ALL));
+        assert_eq!(
+            fs::read_to_string(&files[2]).unwrap(),
+            "DTest.java\ncom/foo/ATest.java\ncom/foo/BTest.java\n"
+        );
+    }
+
+    #[test]
+    fn maven_files_empty_shard() {
+        //given
+        let dir = tempdir().unwrap();
+
+        //when
+        let files = maven_files(&shards()[1..], dir.path()).unwrap();
+
+        //then
+        assert_eq!(
+            fs::read_to_string(&files[0]).unwrap(),
+            "no/tests/in/this/Shard.java\n"
+        );
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "");
+    }
+
+    #[test]
+    fn pytest_files_output() {
+        //given
+        let dir = tempdir().unwrap();
+        let mut shards = shards();
+        shards[0].node_ids = vec![
+            String::from("tests/test_a.py"),
+            String::from("tests/test_b.py::test_one"),
+        ];
+
+        //when
+        let files = pytest_files(&shards, dir.path()).unwrap();
+
+        //then
+        assert_eq!(files.len(), 4);
+        assert!(files[0].ends_with("shard-1.args"));
+        assert_eq!(
+            fs::read_to_string(&files[0]).unwrap(),
+            "tests/test_a.py\ntests/test_b.py::test_one\n"
+        );
+        assert_eq!(fs::read_to_string(&files[1]).unwrap(), "--version\n");
+        assert!(files[2].ends_with("shard-1.deselect"));
+        assert_eq!(fs::read_to_string(&files[2]).unwrap(), "");
+        assert_eq!(
+            fs::read_to_string(&files[3]).unwrap(),
+            "--deselect=tests/test_a.py\n--deselect=tests/test_b.py::test_one\n"
+        );
+    }
+}
@@ -1,22 +1,146 @@
+use crate::error::{self, ReportError};
+use crate::format::{self, Sniff};
 use crate::model::{FilePath, TestSuite};
-use quick_xml::de::from_str;
-use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
-pub fn file_to_report(path: &FilePath) -> Option<TestSuite> {
-    let content = fs::read_to_string(&path.path)
-        .map_err(|_| {
-            eprintln!("Can't read content of file {}", path.path);
-        })
-        .ok()?;
-    from_str::<TestSuite>(&content)
-        .map_err(|_| eprintln!("Can't parse file {}", path.path))
-        .ok()
+/// Beginning of file used to detect its format
+const SNIFF_LENGTH: usize = 64 * 1024;
+
+pub fn file_to_report(path: &FilePath) -> Result<Vec<TestSuite>, ReportError> {
+    let read_error = |source| ReportError::Read {
+        path: path.path.clone(),
+        source,
+    };
+    let file = File::open(&path.path).map_err(read_error)?;
+    let extension = Path::new(&path.path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    let mut reader = BufReader::with_capacity(SNIFF_LENGTH, file);
+    let mut test_suites = read_report(&mut reader, &path.path, extension)?;
+    let unnamed = test_suites.iter().filter(|ts| ts.name.is_empty()).count();
+    let stem = Path::new(&path.path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    // Formats like libtest do not name suites, file name is the best we have
+    test_suites
+        .iter_mut()
+        .filter(|ts| ts.name.is_empty())
+        .enumerate()
+        .for_each(|(index, ts)| {
+            ts.name = match unnamed {
+                1 => stem.clone(),
+                _ => format!("{}-{}", stem, index + 1),
+            }
+        });
+    test_suites
+        .iter_mut()
+        .for_each(|ts| ts.file = path.path.clone());
+    Ok(test_suites)
+}
+
+/// Parses files on `jobs` threads. Results come in order of files, the same as with one thread.
+pub fn files_to_report(
+    paths: &[FilePath],
+    jobs: usize,
+) -> Vec<Result<Vec<TestSuite>, ReportError>> {
+    let next = AtomicUsize::new(0);
+    let worker = || {
+        let mut parsed = Vec::new();
+        // Files are taken one by one, so a few big files do not keep one thread busy alone
+        loop {
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let Some(path) = paths.get(index) else {
+                return parsed;
+            };
+            parsed.push((index, file_to_report(path)));
+        }
+    };
+    let mut reports: Vec<(usize, Result<Vec<TestSuite>, ReportError>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.clamp(1, paths.len().max(1)))
+            .map(|_| scope.spawn(worker))
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    reports.sort_by_key(|(index, _)| *index);
+    reports.into_iter().map(|(_, report)| report).collect()
+}
+
+/// Formats which can stream are detected from beginning of file and never loaded whole,
+/// others are read into memory
+fn read_report(
+    reader: &mut impl BufRead,
+    path: &str,
+    extension: Option<&str>,
+) -> Result<Vec<TestSuite>, ReportError> {
+    let read_error = |source| ReportError::Read {
+        path: path.to_string(),
+        source,
+    };
+    let prefix = reader.fill_buf().map_err(read_error)?;
+    let prefix = match std::str::from_utf8(prefix) {
+        Ok(prefix) => prefix,
+        // Beginning can end in the middle of character
+        Err(e) => std::str::from_utf8(&prefix[..e.valid_up_to()]).unwrap(),
+    };
+    let streaming = format::detect(&Sniff::new(prefix, extension)).filter(|f| f.streams());
+    if let Some(format) = streaming {
+        return format.parse_reader(reader).map_err(|e| {
+            // Stream is gone, position is found by reading the file again
+            let position = e.offset.and_then(|offset| {
+                let file = File::open(path).ok()?;
+                error::line_column(file, offset).ok()
+            });
+            parse_error(path, format.name(), e.message, position)
+        });
+    }
+    let mut content = String::new();
+    reader.read_to_string(&mut content).map_err(read_error)?;
+    report(&Sniff::new(&content, extension), path)
+}
+
+fn parse_error(
+    path: &str,
+    format: &'static str,
+    message: String,
+    position: Option<(usize, usize)>,
+) -> ReportError {
+    ReportError::Parse {
+        path: path.to_string(),
+        format,
+        message,
+        position,
+    }
+}
+
+fn report(sniff: &Sniff, path: &str) -> Result<Vec<TestSuite>, ReportError> {
+    match format::detect(sniff) {
+        Some(format) => format.parse(sniff.content).map_err(|e| {
+            let position = e
+                .offset
+                .and_then(|offset| error::line_column(sniff.content.as_bytes(), offset).ok());
+            parse_error(path, format.name(), e.message, position)
+        }),
+        None => Err(ReportError::UnknownFormat {
+            path: path.to_string(),
+            root: sniff.root.clone(),
+        }),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs::File;
+    use crate::duration::Duration;
+    use crate::model::{Outcome, OutcomeDetails};
+    use std::fs::{self, File};
     use std::io::Write;
     use tempfile::tempdir;
 
@@ -46,7 +170,7 @@ mod tests {
         });
 
         //then
-        assert!(result.is_some()); // Check if we get a Some(TestSuite)
+        assert_eq!(result.map(|suites| suites.len()).ok(), Some(1)); // Check if we get a Some(TestSuite)
     }
 
     #[test]
@@ -57,7 +181,7 @@ mod tests {
         });
 
         //then
-        assert!(result.is_none()); // Expect None for non-existent file
+        assert!(matches!(result, Err(ReportError::Read { .. }))); // Expect error for non-existent file
     }
 
     #[test]
@@ -72,6 +196,335 @@ mod tests {
         let result = file_to_report(&FilePath {
             path: path.to_string_lossy().into_owned(),
         });
-        assert!(result.is_none()); // Expect None for invalid JSON
+        assert!(matches!(
+            result,
+            Err(ReportError::UnknownFormat { root: None, .. })
+        )); // Expect error for invalid JSON
+    }
+
+    #[test]
+    fn test_file_to_report_error_position() {
+        //given
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("TEST-a.xml");
+        fs::write(
+            &path,
+            "<testsuite name=\"a\" time=\"1.0\">\n    <testcase name=\"t\" time=\"1.0\"/>\n</testsuite>",
+        )
+        .unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        //when
+        let result = file_to_report(&FilePath { path: path.clone() });
+
+        //then
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!(
+                "{}:2:36: invalid JUnit report: missing attribute classname of testcase",
+                path
+            )
+        );
+    }
+
+    #[test]
+    fn test_report_testsuites_root() {
+        //given
+        let content = r#"
+<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="all" tests="3" time="6.0">
+    <testsuite name="scenario.SearchTest" tests="2" time="3.0">
+        <testcase name="testSearchQuery" classname="scenario.SearchTest" time="1.0"/>
+        <testcase name="testSearchRegex" classname="scenario.SearchTest" time="2.0"/>
+    </testsuite>
+    <testsuite name="scenario.LoginTest" tests="1" time="3.0">
+        <testcase name="testLogin" classname="scenario.LoginTest" time="3.0"/>
+    </testsuite>
+</testsuites>"#;
+
+        //when
+        let result = report(&Sniff::new(content, None), "").unwrap();
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "scenario.SearchTest");
+        assert_eq!(result[0].test_cases.len(), 2);
+        assert_eq!(result[0].parents, vec!["all".to_string()]);
+        assert_eq!(result[1].name, "scenario.LoginTest");
+        assert_eq!(result[1].time.as_secs_f64(), 3.0);
+    }
+
+    #[test]
+    fn test_report_nested_suites() {
+        //given
+        let content = r#"
+<testsuites>
+    <testsuite name="module" time="7.0">
+        <testcase name="testOwn" classname="module" time="1.0"/>
+        <testsuite name="scenario.SearchTest" time="2.0">
+            <testcase name="testSearch" classname="scenario.SearchTest" time="2.0"/>
+        </testsuite>
+        <testsuite name="inner" time="4.0">
+            <testsuite name="scenario.LoginTest" time="4.0">
+                <testcase name="testLogin" classname="scenario.LoginTest" time="4.0"/>
+            </testsuite>
+        </testsuite>
+    </testsuite>
+</testsuites>"#;
+
+        //when
+        let result = report(&Sniff::new(content, None), "").unwrap();
+
+        //then
+        let names: Vec<&str> = result.iter().map(|ts| ts.name.as_str()).collect();
+        assert_eq!(names, vec!["module", "scenario.SearchTest", "scenario.LoginTest"]);
+        assert_eq!(result[0].time.as_secs_f64(), 1.0);
+        assert!(result[0].parents.is_empty());
+        assert_eq!(result[1].parents, vec!["module".to_string()]);
+        assert_eq!(
+            result[2].parents,
+            vec!["module".to_string(), "inner".to_string()]
+        );
+        assert_eq!(
+            result.iter().map(|ts| ts.time).sum::<Duration>(),
+            Duration::from_secs(7)
+        );
+    }
+
+    #[test]
+    fn test_report_empty_testsuites() {
+        //when
+        let result = report(&Sniff::new("<testsuites/>", None), "").unwrap();
+
+        //then
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_report_outcomes() {
+        //given
+        let content = r#"
+<testsuite name="scenario.SearchTest" tests="4" failures="1" errors="1" skipped="1" time="4.0">
+    <testcase name="testPassed" classname="scenario.SearchTest" time="1.0"/>
+    <testcase name="testFailed" classname="scenario.SearchTest" time="1.0">
+        <failure message="expected 1 but was 2" type="java.lang.AssertionError"><![CDATA[stack trace]]></failure>
+        <system-out><![CDATA[output]]></system-out>
+    </testcase>
+    <testcase name="testErrored" classname="scenario.SearchTest" time="1.0">
+        <error message="boom" type="java.lang.NullPointerException">trace</error>
+    </testcase>
+    <testcase name="testSkipped" classname="scenario.SearchTest" time="1.0">
+        <skipped/>
+    </testcase>
+</testsuite>"#;
+
+        //when
+        let result = report(&Sniff::new(content, None), "").unwrap();
+
+        //then
+        let test_cases = &result[0].test_cases;
+        assert_eq!(test_cases[0].outcome, Outcome::Passed);
+        assert_eq!(
+            test_cases[1].outcome,
+            Outcome::Failed(OutcomeDetails {
+                message: Some("expected 1 but was 2".to_string()),
+                kind: Some("java.lang.AssertionError".to_string()),
+                text: "stack trace".to_string(),
+            })
+        );
+        assert_eq!(
+            test_cases[2].outcome,
+            Outcome::Errored(OutcomeDetails {
+                message: Some("boom".to_string()),
+                kind: Some("java.lang.NullPointerException".to_string()),
+                text: "trace".to_string(),
+            })
+        );
+        assert_eq!(test_cases[3].outcome, Outcome::Skipped(OutcomeDetails::default()));
+        assert!(result[0].check_counters().is_empty());
+        assert_eq!(result[0].time_without_skipped(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_report_counters_mismatch() {
+        //given
+        let content = r#"
+<testsuite name="scenario.SearchTest" tests="3" failures="1" time="1.0">
+    <testcase name="testPassed" classname="scenario.SearchTest" time="1.0"/>
+</testsuite>"#;
+
+        //when
+        let result = report(&Sniff::new(content, None), "").unwrap();
+
+        //then
+        assert_eq!(
+            result[0].check_counters(),
+            vec![
+                "suite scenario.SearchTest declares tests=3 but contains 1".to_string(),
+                "suite scenario.SearchTest declares failures=1 but contains 0".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_missing_suite_time() {
+        //given
+        let content = r#"
+<testsuite name="scenario.SearchTest" tests="2">
+    <testcase name="testFirst" classname="scenario.SearchTest" time="1,5"/>
+    <testcase name="testSecond" classname="scenario.SearchTest" time="250ms"/>
+</testsuite>"#;
+
+        //when
+        let result = report(&Sniff::new(content, None), "").unwrap();
+
+        //then
+        assert_eq!(result[0].time.as_secs_f64(), 1.75);
+        assert_eq!(
+            result[0].warnings,
+            vec!["suite scenario.SearchTest has no time, using sum of its test cases 1.75s"]
+        );
+    }
+
+    #[test]
+    fn test_report_sniffs_root_element() {
+        //given
+        let nunit = r#"<test-run><test-suite type="TestFixture" fullname="Calc.CalcTests" duration="1.0"/></test-run>"#;
+
+        //when
+        let result = report(&Sniff::new(nunit, None), "").unwrap();
+        let unknown = report(&Sniff::new("<html><body/></html>", None), "");
+        let tap = report(&Sniff::new("ok 1 - renders <div/>\n", None), "").unwrap();
+
+        //then
+        assert_eq!(result[0].name, "Calc.CalcTests");
+        assert_eq!(tap[0].test_cases[0].name, "renders <div/>");
+        assert!(matches!(
+            unknown,
+            Err(ReportError::UnknownFormat { root: Some(root), .. }) if root == "html"
+        ));
+    }
+
+    #[test]
+    fn test_file_to_report_names_libtest_suites_after_file() {
+        //given
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("my-crate.json");
+        let mut file = File::create(&path).unwrap();
+        writeln!(
+            file,
+            r#"{{ "type": "suite", "event": "started", "test_count": 1 }}
+{{ "type": "test", "name": "tests::works", "event": "ok", "exec_time": 0.5 }}
+{{ "type": "suite", "event": "ok", "exec_time": 0.5 }}
+{{ "type": "suite", "event": "started", "test_count": 1 }}
+{{ "type": "suite", "event": "ok", "exec_time": 0.0 }}"#
+        )
+        .unwrap();
+
+        //when
+        let result = file_to_report(&FilePath {
+            path: path.to_string_lossy().into_owned(),
+        })
+        .unwrap();
+
+        //then
+        let names: Vec<&str> = result.iter().map(|ts| ts.name.as_str()).collect();
+        assert_eq!(names, vec!["my-crate-1", "my-crate-2"]);
+        assert_eq!(result[0].test_cases[0].name, "tests::works");
+    }
+
+    /// Writes `count` Surefire-like reports with a few test cases and some output each
+    fn synthetic_corpus(dir: &Path, count: usize) -> Vec<FilePath> {
+        (0..count)
+            .map(|i| {
+                let path = dir.join(format!("TEST-com.example.Generated{}Test.xml", i));
+                let cases: String = (0..10)
+                    .map(|c| {
+                        format!(
+                            r#"<testcase name="test{}" classname="com.example.Generated{}Test" time="0.{}"><system-out><![CDATA[{}]]></system-out></testcase>"#,
+                            c,
+                            i,
+                            c,
+                            "output line\n".repeat(20)
+                        )
+                    })
+                    .collect();
+                fs::write(
+                    &path,
+                    format!(
+                        r#"<testsuite name="com.example.Generated{}Test" tests="10" time="{}.5">{}</testsuite>"#,
+                        i, i, cases
+                    ),
+                )
+                .unwrap();
+                FilePath {
+                    path: path.to_string_lossy().into_owned(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_files_to_report_keeps_order_of_files() {
+        //given
+        let dir = tempdir().unwrap();
+        let mut paths = synthetic_corpus(dir.path(), 50);
+        paths.insert(
+            10,
+            FilePath {
+                path: "non_existent_file.xml".to_string(),
+            },
+        );
+
+        //when
+        let sequential = files_to_report(&paths, 1);
+        let parallel = files_to_report(&paths, 8);
+
+        //then
+        assert_eq!(parallel.len(), 51);
+        assert!(parallel[10].is_err());
+        let suites = |reports: Vec<Result<Vec<TestSuite>, ReportError>>| {
+            reports
+                .into_iter()
+                .flat_map(|report| report.unwrap_or_default())
+                .collect::<Vec<_>>()
+        };
+        let parallel = suites(parallel);
+        assert_eq!(parallel, suites(sequential));
+        assert_eq!(parallel[10].name, "com.example.Generated10Test");
+    }
+
+    /// Run with `cargo test --release benchmark_files_to_report -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn benchmark_files_to_report() {
+        //given
+        let dir = tempdir().unwrap();
+        let paths = synthetic_corpus(dir.path(), 20_000);
+        let cpus = std::thread::available_parallelism().map_or(1, |count| count.get());
+        let mut job_counts = vec![1, 2, 4, cpus];
+        job_counts.sort();
+        job_counts.dedup();
+
+        //when
+        let mut sequential = std::time::Duration::ZERO;
+        for jobs in job_counts {
+            let start = std::time::Instant::now();
+            let reports = files_to_report(&paths, jobs);
+            let elapsed = start.elapsed();
+            if jobs == 1 {
+                sequential = elapsed;
+            }
+
+            //then
+            assert!(reports.iter().all(|report| report.is_ok()));
+            println!(
+                "{} files, {} jobs: {:?}, speedup {:.2}",
+                paths.len(),
+                jobs,
+                elapsed,
+                sequential.as_secs_f64() / elapsed.as_secs_f64()
+            );
+        }
     }
 }
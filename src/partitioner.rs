@@ -0,0 +1,580 @@
+use crate::duration::Duration;
+use crate::model::TimeByLetter;
+use crate::processing;
+use clap::ValueEnum;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+/// Splits units into exactly `group_count` groups. Groups are allowed to be empty when there
+/// are fewer units than groups.
+pub trait Partitioner {
+    fn partition(&self, group_count: u16, units: Vec<TimeByLetter>) -> Vec<Vec<TimeByLetter>>;
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Strategy {
+    /// Greedy pass in alphabetical order, groups are ranges of letters
+    #[default]
+    Sequential,
+    /// Longest processing time first, every unit goes to the least loaded group
+    Lpt,
+    /// Karmarkar–Karp largest differencing method
+    KarmarkarKarp,
+    /// Exact search, falls back to best found solution on large inputs
+    BranchAndBound,
+    /// Optimal split into ranges of letters, keeps alphabetical order
+    Linear,
+}
+
+impl Strategy {
+    pub fn partitioner(self) -> Box<dyn Partitioner> {
+        match self {
+            Strategy::Sequential => Box::new(Sequential),
+            Strategy::Lpt => Box::new(Lpt),
+            Strategy::KarmarkarKarp => Box::new(KarmarkarKarp),
+            Strategy::BranchAndBound => Box::new(BranchAndBound::default()),
+            Strategy::Linear => Box::new(Linear),
+        }
+    }
+}
+
+pub fn group_time(group: &[TimeByLetter]) -> Duration {
+    group.iter().map(|tbl| tbl.time).sum()
+}
+
+/// Longest group of partition, value which all strategies try to minimize
+pub fn makespan(groups: &[Vec<TimeByLetter>]) -> Duration {
+    groups
+        .iter()
+        .map(|g| group_time(g))
+        .max()
+        .unwrap_or_default()
+}
+
+/// Orders members by letter and groups by their first member, empty groups go last.
+/// Keeps output readable and the same for the same input.
+fn normalize(mut groups: Vec<Vec<TimeByLetter>>) -> Vec<Vec<TimeByLetter>> {
+    groups
+        .iter_mut()
+        .for_each(|g| g.sort_by(|a, b| a.key.cmp(&b.key)));
+    groups.sort_by(|a, b| match (a.first(), b.first()) {
+        (Some(a), Some(b)) => a.key.cmp(&b.key),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+    groups
+}
+
+/// Units ordered from the longest, ties broken by letter
+fn sorted_descending(mut units: Vec<TimeByLetter>) -> Vec<TimeByLetter> {
+    units.sort_by(|a, b| b.time.cmp(&a.time).then(a.key.cmp(&b.key)));
+    units
+}
+
+/// Current behaviour of [processing::divide_into_groups], adjusted to requested group count by
+/// merging or splitting neighbouring groups, so letter ranges are kept.
+pub struct Sequential;
+
+impl Partitioner for Sequential {
+    fn partition(&self, group_count: u16, units: Vec<TimeByLetter>) -> Vec<Vec<TimeByLetter>> {
+        let group_count = group_count.max(1) as usize;
+        let mut groups = processing::divide_into_groups(group_count as u16, units);
+        while groups.len() > group_count {
+            let index = (0..groups.len() - 1)
+                .min_by(|&a, &b| {
+                    let merged = |i: usize| group_time(&groups[i]) + group_time(&groups[i + 1]);
+                    merged(a).cmp(&merged(b))
+                })
+                .unwrap();
+            let next = groups.remove(index + 1);
+            groups[index].extend(next);
+        }
+        while groups.len() < group_count {
+            let Some(index) = (0..groups.len())
+                .filter(|&i| groups[i].len() > 1)
+                .max_by_key(|&i| group_time(&groups[i]))
+            else {
+                break;
+            };
+            let total = group_time(&groups[index]);
+            let split_at = (1..groups[index].len())
+                .min_by_key(|&at| {
+                    let left = group_time(&groups[index][..at]);
+                    left.max(total - left)
+                })
+                .unwrap();
+            let tail = groups[index].split_off(split_at);
+            groups.insert(index + 1, tail);
+        }
+        groups.resize_with(group_count, Vec::new);
+        groups
+    }
+}
+
+pub struct Lpt;
+
+impl Partitioner for Lpt {
+    fn partition(&self, group_count: u16, units: Vec<TimeByLetter>) -> Vec<Vec<TimeByLetter>> {
+        let mut groups: Vec<(Duration, Vec<TimeByLetter>)> =
+            vec![(Duration::ZERO, Vec::new()); group_count.max(1) as usize];
+        for unit in sorted_descending(units) {
+            let lightest = groups.iter_mut().min_by_key(|g| g.0).unwrap();
+            lightest.0 += unit.time;
+            lightest.1.push(unit);
+        }
+        normalize(groups.into_iter().map(|(_, g)| g).collect())
+    }
+}
+
+/// Partial solution of Karmarkar–Karp, subsets are kept sorted from the longest
+struct Differencing {
+    subsets: Vec<(Duration, Vec<TimeByLetter>)>,
+    /// Order of creation, makes ties in heap deterministic
+    sequence: usize,
+}
+
+impl Differencing {
+    fn spread(&self) -> Duration {
+        self.subsets.first().unwrap().0 - self.subsets.last().unwrap().0
+    }
+}
+
+impl PartialEq for Differencing {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Differencing {}
+
+impl PartialOrd for Differencing {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Differencing {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.spread()
+            .cmp(&other.spread())
+            .then(other.sequence.cmp(&self.sequence))
+    }
+}
+
+pub struct KarmarkarKarp;
+
+impl Partitioner for KarmarkarKarp {
+    fn partition(&self, group_count: u16, units: Vec<TimeByLetter>) -> Vec<Vec<TimeByLetter>> {
+        let group_count = group_count.max(1) as usize;
+        let mut heap: BinaryHeap<Differencing> = sorted_descending(units)
+            .into_iter()
+            .enumerate()
+            .map(|(sequence, unit)| {
+                let mut subsets = vec![(Duration::ZERO, Vec::new()); group_count];
+                subsets[0] = (unit.time, vec![unit]);
+                Differencing { subsets, sequence }
+            })
+            .collect();
+        let mut sequence = heap.len();
+        while heap.len() > 1 {
+            let first = heap.pop().unwrap();
+            let second = heap.pop().unwrap();
+            // The longest subset of one partition is joined with the shortest of the other
+            let mut subsets: Vec<(Duration, Vec<TimeByLetter>)> = first
+                .subsets
+                .into_iter()
+                .zip(second.subsets.into_iter().rev())
+                .map(|((t1, mut g1), (t2, g2))| {
+                    g1.extend(g2);
+                    (t1 + t2, g1)
+                })
+                .collect();
+            subsets.sort_by_key(|s| Reverse(s.0));
+            heap.push(Differencing { subsets, sequence });
+            sequence += 1;
+        }
+        let groups = heap
+            .pop()
+            .map(|d| d.subsets.into_iter().map(|(_, g)| g).collect())
+            .unwrap_or_else(|| vec![Vec::new(); group_count]);
+        normalize(groups)
+    }
+}
+
+/// Depth first search over assignments of units to groups, pruned by the best known solution.
+/// Starts from LPT result and gives up after `node_limit` visited nodes, returning the best
+/// partition found so far, which makes it usable also for inputs too big to solve exactly.
+pub struct BranchAndBound {
+    pub node_limit: usize,
+}
+
+impl Default for BranchAndBound {
+    fn default() -> Self {
+        BranchAndBound {
+            node_limit: 1_000_000,
+        }
+    }
+}
+
+struct Search {
+    times: Vec<Duration>,
+    loads: Vec<Duration>,
+    assignment: Vec<usize>,
+    best_makespan: Duration,
+    best_assignment: Option<Vec<usize>>,
+    lower_bound: Duration,
+    nodes_left: usize,
+}
+
+impl Search {
+    fn visit(&mut self, index: usize) {
+        if self.nodes_left == 0 || self.best_makespan <= self.lower_bound {
+            return;
+        }
+        self.nodes_left -= 1;
+        if index == self.times.len() {
+            let makespan = self.loads.iter().copied().max().unwrap_or_default();
+            if makespan < self.best_makespan {
+                self.best_makespan = makespan;
+                self.best_assignment = Some(self.assignment.clone());
+            }
+            return;
+        }
+        let time = self.times[index];
+        for group in 0..self.loads.len() {
+            // Groups with equal load are interchangeable, trying one of them is enough
+            if self.loads[..group].contains(&self.loads[group]) {
+                continue;
+            }
+            if self.loads[group] + time >= self.best_makespan {
+                continue;
+            }
+            self.loads[group] += time;
+            self.assignment[index] = group;
+            self.visit(index + 1);
+            self.loads[group] -= time;
+        }
+    }
+}
+
+impl Partitioner for BranchAndBound {
+    fn partition(&self, group_count: u16, units: Vec<TimeByLetter>) -> Vec<Vec<TimeByLetter>> {
+        let group_count = group_count.max(1) as usize;
+        let initial = Lpt.partition(group_count as u16, units.clone());
+        let units = sorted_descending(units);
+        let times: Vec<Duration> = units.iter().map(|u| u.time).collect();
+        let total: Duration = times.iter().sum();
+        let lower_bound = times
+            .first()
+            .copied()
+            .unwrap_or_default()
+            .max(total / group_count as u32);
+        let mut search = Search {
+            assignment: vec![0; times.len()],
+            times,
+            loads: vec![Duration::ZERO; group_count],
+            best_makespan: makespan(&initial),
+            best_assignment: None,
+            lower_bound,
+            nodes_left: self.node_limit,
+        };
+        search.visit(0);
+        match search.best_assignment {
+            Some(assignment) => {
+                let mut groups = vec![Vec::new(); group_count];
+                units
+                    .into_iter()
+                    .zip(assignment)
+                    .for_each(|(unit, group)| groups[group].push(unit));
+                normalize(groups)
+            }
+            None => initial,
+        }
+    }
+}
+
+/// Linear partition: contiguous split of ordered units minimizing the longest group.
+/// Dynamic programming over prefix sums, best cut point only moves forward as more units are
+/// covered, so every group count is solved in a single pass.
+pub struct Linear;
+
+impl Partitioner for Linear {
+    fn partition(&self, group_count: u16, units: Vec<TimeByLetter>) -> Vec<Vec<TimeByLetter>> {
+        let group_count = group_count.max(1) as usize;
+        let n = units.len();
+        if n <= group_count {
+            let mut groups: Vec<Vec<TimeByLetter>> = units.into_iter().map(|u| vec![u]).collect();
+            groups.resize_with(group_count, Vec::new);
+            return groups;
+        }
+        let mut prefix = vec![Duration::ZERO; n + 1];
+        for (i, unit) in units.iter().enumerate() {
+            prefix[i + 1] = prefix[i] + unit.time;
+        }
+        // longest[j][i] is the best makespan of first i units divided into j + 1 groups,
+        // cut[j][i] is where the last of these groups starts
+        let mut longest = vec![vec![Duration::MAX; n + 1]; group_count];
+        let mut cut = vec![vec![0; n + 1]; group_count];
+        longest[0][1..].copy_from_slice(&prefix[1..]);
+        for j in 1..group_count {
+            let (done, rest) = longest.split_at_mut(j);
+            let previous = &done[j - 1];
+            let cost = |m: usize, i: usize| previous[m].max(prefix[i] - prefix[m]);
+            let mut m = j;
+            for i in j + 1..=n {
+                while m + 1 < i && cost(m + 1, i) <= cost(m, i) {
+                    m += 1;
+                }
+                rest[0][i] = cost(m, i);
+                cut[j][i] = m;
+            }
+        }
+        let mut bounds = vec![n];
+        for j in (1..group_count).rev() {
+            bounds.push(cut[j][*bounds.last().unwrap()]);
+        }
+        bounds.push(0);
+        bounds.reverse();
+        let mut units = units.into_iter();
+        bounds
+            .windows(2)
+            .map(|w| units.by_ref().take(w[1] - w[0]).collect())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letters(times: &[f64]) -> Vec<TimeByLetter> {
+        times
+            .iter()
+            .zip('A'..='Z')
+            .map(|(time, letter)| TimeByLetter::new(*time, letter))
+            .collect()
+    }
+
+    fn all_strategies() -> Vec<Strategy> {
+        Strategy::value_variants().to_vec()
+    }
+
+    #[test]
+    fn every_strategy_returns_requested_group_count() {
+        for strategy in all_strategies() {
+            for count in [1, 4, 5, 7, 30] {
+                //when
+                let groups = strategy
+                    .partitioner()
+                    .partition(count, letters(&[10.0; 26]));
+
+                //then
+                assert_eq!(groups.len(), count as usize, "{:?}", strategy);
+                assert_eq!(groups.iter().flatten().count(), 26, "{:?}", strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn every_strategy_handles_empty_input() {
+        for strategy in all_strategies() {
+            //when
+            let groups = strategy.partitioner().partition(3, vec![]);
+
+            //then
+            assert_eq!(groups, vec![vec![], vec![], vec![]], "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn every_strategy_is_deterministic() {
+        //given
+        let times = [5.0, 3.0, 5.0, 1.0, 3.0, 5.0, 2.0, 2.0, 4.0, 1.0];
+        let expected = [
+            (Strategy::Sequential, ["AB", "CDE", "FGHIJ"]),
+            (Strategy::Lpt, ["ADIJ", "BCG", "EFH"]),
+            (Strategy::KarmarkarKarp, ["AHI", "BCG", "DEFJ"]),
+            (Strategy::BranchAndBound, ["ADIJ", "BCG", "EFH"]),
+            (Strategy::Linear, ["ABC", "DEFGH", "IJ"]),
+        ];
+        let keys = |groups: Vec<Vec<TimeByLetter>>| -> Vec<String> {
+            groups
+                .iter()
+                .map(|g| g.iter().map(|tbl| tbl.key.as_str()).collect())
+                .collect()
+        };
+        for (strategy, groups) in expected {
+            //when
+            let result = strategy.partitioner().partition(3, letters(&times));
+
+            //then
+            assert_eq!(keys(result), groups, "{:?}", strategy);
+            // Ranges keep input order
+            if matches!(strategy, Strategy::Sequential | Strategy::Linear) {
+                continue;
+            }
+            // Units with equal times must not be ordered by their position in input
+            let mut shuffled = letters(&times);
+            shuffled.reverse();
+            shuffled.rotate_left(3);
+            let result = strategy.partitioner().partition(3, shuffled);
+            assert_eq!(keys(result), groups, "{:?}", strategy);
+        }
+        assert_eq!(expected.len(), all_strategies().len());
+    }
+
+    #[test]
+    fn sequential_merges_extra_groups() {
+        //when
+        let groups = Sequential.partition(4, letters(&[10.0; 26]));
+
+        //then
+        let sizes: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+        assert_eq!(sizes, vec![6, 6, 6, 8]);
+    }
+
+    #[test]
+    fn sequential_splits_to_requested_count() {
+        //when
+        let groups = Sequential.partition(3, letters(&[1.0, 1.0, 100.0]));
+
+        //then
+        assert_eq!(
+            groups,
+            vec![
+                vec![TimeByLetter::new(1.0, 'A')],
+                vec![TimeByLetter::new(1.0, 'B')],
+                vec![TimeByLetter::new(100.0, 'C')],
+            ]
+        );
+    }
+
+    #[test]
+    fn lpt_assigns_longest_first() {
+        //when
+        let groups = Lpt.partition(2, letters(&[3.0, 3.0, 2.0, 2.0, 2.0]));
+
+        //then
+        assert_eq!(
+            groups,
+            vec![
+                vec![
+                    TimeByLetter::new(3.0, 'A'),
+                    TimeByLetter::new(2.0, 'C'),
+                    TimeByLetter::new(2.0, 'E'),
+                ],
+                vec![TimeByLetter::new(3.0, 'B'), TimeByLetter::new(2.0, 'D')],
+            ]
+        );
+    }
+
+    #[test]
+    fn karmarkar_karp_differencing() {
+        //when
+        let groups = KarmarkarKarp.partition(2, letters(&[8.0, 7.0, 6.0, 5.0, 4.0]));
+
+        //then
+        let mut times: Vec<Duration> = groups.iter().map(|g| group_time(g)).collect();
+        times.sort();
+        assert_eq!(
+            times,
+            vec![Duration::from_secs(14), Duration::from_secs(16)]
+        );
+    }
+
+    #[test]
+    fn branch_and_bound_finds_optimum() {
+        //when
+        let lpt = Lpt.partition(2, letters(&[3.0, 3.0, 2.0, 2.0, 2.0]));
+        let exact = BranchAndBound::default().partition(2, letters(&[3.0, 3.0, 2.0, 2.0, 2.0]));
+
+        //then
+        assert_eq!(makespan(&lpt), Duration::from_secs(7));
+        assert_eq!(makespan(&exact), Duration::from_secs(6));
+    }
+
+    #[test]
+    fn branch_and_bound_optimum_for_three_groups() {
+        //when
+        let groups =
+            BranchAndBound::default().partition(3, letters(&[8.0, 7.0, 6.0, 5.0, 4.0, 3.0, 3.0]));
+
+        //then
+        assert_eq!(makespan(&groups), Duration::from_secs(12));
+    }
+
+    #[test]
+    fn branch_and_bound_respects_node_limit() {
+        //when
+        let groups =
+            BranchAndBound { node_limit: 0 }.partition(2, letters(&[3.0, 3.0, 2.0, 2.0, 2.0]));
+
+        //then
+        assert_eq!(makespan(&groups), Duration::from_secs(7));
+    }
+
+    #[test]
+    fn linear_keeps_order_and_minimizes_longest_group() {
+        //when
+        let groups = Linear.partition(3, letters(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0]));
+
+        //then
+        let times: Vec<f64> = groups.iter().map(|g| group_time(g).as_secs_f64()).collect();
+        assert_eq!(times, vec![15.0, 13.0, 17.0]);
+        assert_eq!(groups[0].last().unwrap().key, "E");
+        assert_eq!(groups[1].last().unwrap().key, "G");
+    }
+
+    #[test]
+    fn linear_beats_sequential_greedy() {
+        //given
+        let units = letters(&[10.0; 26]);
+
+        //when
+        let sequential = Sequential.partition(4, units.clone());
+        let linear = Linear.partition(4, units);
+
+        //then
+        assert_eq!(makespan(&sequential), Duration::from_secs(80));
+        assert_eq!(makespan(&linear), Duration::from_secs(70));
+        assert!(linear.iter().all(|g| !g.is_empty()));
+    }
+
+    #[test]
+    fn linear_matches_brute_force() {
+        //given
+        let mut seed: u32 = 7;
+        let mut random = move || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            ((seed >> 16) % 20) as f64
+        };
+        for n in 1..9 {
+            for count in 1..5u16 {
+                let times: Vec<f64> = (0..n).map(|_| random()).collect();
+
+                //when
+                let groups = Linear.partition(count, letters(&times));
+
+                //then
+                let flattened: Vec<TimeByLetter> = groups.iter().flatten().cloned().collect();
+                assert_eq!(flattened, letters(&times));
+                assert_eq!(groups.len(), count as usize);
+                assert_eq!(
+                    makespan(&groups).as_secs_f64(),
+                    brute_force_contiguous(&times, count as usize)
+                );
+            }
+        }
+    }
+
+    fn brute_force_contiguous(times: &[f64], groups: usize) -> f64 {
+        if groups == 1 || times.is_empty() {
+            return times.iter().sum();
+        }
+        (0..=times.len())
+            .map(|at| {
+                let head: f64 = times[..at].iter().sum();
+                head.max(brute_force_contiguous(&times[at..], groups - 1))
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+}
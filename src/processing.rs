@@ -1,16 +1,100 @@
-use crate::model::{TestSuite, TimeByLetter};
+use crate::duration::Duration;
+use crate::model::{TestCase, TestSuite, TimeByLetter};
+use regex::Regex;
 use std::collections::BTreeMap;
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+
+/// What single unit distributed between groups is
+#[derive(Debug, Clone)]
+pub enum Grouping {
+    FirstLetter(LetterPolicy),
+    /// Full class name
+    Class,
+    /// Package cut to given number of segments
+    Package(usize),
+    /// Build module the report comes from
+    Module,
+    /// Given number of first characters of class name
+    Prefix(usize),
+    /// First capture group (or whole match) of regex applied to full class name
+    Regex(Regex),
+    /// Every test case on its own
+    TestCase,
+}
+
+/// How first letter of class name becomes a bucket
+#[derive(Debug, Clone)]
+pub struct LetterPolicy {
+    /// Put lowercase and uppercase letters into the same bucket
+    pub fold_case: bool,
+    /// Accented letters go to bucket of base letter, other alphabets get own buckets.
+    /// Without it every non ASCII letter goes to the other bucket.
+    pub unicode: bool,
+    /// Bucket for names starting with digit, `_`, other symbol or empty names
+    pub other_bucket: String,
+}
+
+impl Default for LetterPolicy {
+    fn default() -> Self {
+        LetterPolicy {
+            fold_case: true,
+            unicode: false,
+            other_bucket: String::from("#"),
+        }
+    }
+}
+
+impl LetterPolicy {
+    /// Bucket for given name, `None` means the other bucket
+    fn bucket(&self, name: &str) -> Option<char> {
+        let first = simple_name(name).chars().next()?;
+        let first = if self.unicode {
+            first.nfd().next().unwrap_or(first)
+        } else {
+            first
+        };
+        let first = if self.fold_case {
+            first.to_uppercase().next().unwrap_or(first)
+        } else {
+            first
+        };
+        if first.is_ascii_alphabetic() || (self.unicode && first.is_alphabetic()) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+}
+
+/// Extensions of test files which Jest, Mocha, pytest and similar tools use as suite names
+const TEST_FILE_EXTENSIONS: [&str; 10] = [
+    "js", "jsx", "mjs", "cjs", "ts", "tsx", "mts", "cts", "py", "rb",
+];
+
+/// Class name without package. Suites named by test file path, like `src/calc.test.js` or
+/// `tests/test_api.py`, give file name without extensions and pytest `test_` prefix instead.
+fn simple_name(name: &str) -> &str {
+    let (rest, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    if name.contains(['/', '\\']) || TEST_FILE_EXTENSIONS.contains(&extension) {
+        let file = rest.rsplit(['/', '\\']).next().unwrap();
+        let stem = file.split('.').next().unwrap();
+        return stem.strip_prefix("test_").unwrap_or(stem);
+    }
+    #[allow(clippy::double_ended_iterator_last)]
+    name.split('.').last().unwrap()
+}
 
 pub fn divide_into_groups(
     group_count: u16,
     times_by_letters: Vec<TimeByLetter>,
 ) -> Vec<Vec<TimeByLetter>> {
-    let total_time: f32 = times_by_letters.iter().map(|t| t.time).sum();
-    let target_duration = total_time / group_count as f32;
+    let total_time: Duration = times_by_letters.iter().map(|t| t.time).sum();
+    let target_duration = total_time / u32::from(group_count);
 
     let mut result: Vec<Vec<TimeByLetter>> = Vec::new();
     let mut current_group: Vec<TimeByLetter> = Vec::new();
-    let mut current_sum = 0.0;
+    let mut current_sum = Duration::ZERO;
 
     for time_by_letter in times_by_letters {
         if time_by_letter.time > target_duration {
@@ -18,7 +102,7 @@ pub fn divide_into_groups(
             if !current_group.is_empty() {
                 result.push(current_group);
                 current_group = Vec::new();
-                current_sum = 0.0;
+                current_sum = Duration::ZERO;
             }
             result.push(vec![time_by_letter]);
         } else {
@@ -26,7 +110,7 @@ pub fn divide_into_groups(
             if current_sum + time_by_letter.time > target_duration && !current_group.is_empty() {
                 result.push(current_group);
                 current_group = Vec::new();
-                current_sum = 0.0;
+                current_sum = Duration::ZERO;
             }
             current_sum += time_by_letter.time;
             current_group.push(time_by_letter);
@@ -41,59 +125,201 @@ pub fn divide_into_groups(
     result
 }
 
-fn duration(test_suites: &Vec<TestSuite>) -> f32 {
-    test_suites.iter().map(|ts| ts.time).sum()
+fn duration<'a>(test_suites: impl IntoIterator<Item = &'a TestSuite>) -> Duration {
+    test_suites
+        .into_iter()
+        .map(|ts| ts.time_without_skipped())
+        .sum()
 }
 
-pub fn group_by_first_letter(vec: Vec<TestSuite>) -> Vec<TimeByLetter> {
-    let mut groups: BTreeMap<char, Vec<TestSuite>> = BTreeMap::new();
+/// Buckets `A`..`Z` are always present, other letters follow them and the other bucket is last
+pub fn group_by_first_letter(vec: &[TestSuite], policy: &LetterPolicy) -> Vec<TimeByLetter> {
+    let mut groups: BTreeMap<char, Vec<&TestSuite>> = BTreeMap::new();
     ('A'..='Z').for_each(|c| {
         groups.insert(c, Vec::new());
     });
+    let mut other: Vec<&TestSuite> = Vec::new();
     for item in vec {
-        let first_letter = item
-            .name
-            .split('.')
-            .last()
-            .unwrap()
-            .chars()
-            .next()
-            .unwrap_or('0');
-        groups
-            .entry(first_letter.clone())
-            .or_insert_with(Vec::new)
-            .push(item)
+        match policy.bucket(&item.name) {
+            Some(first_letter) => groups.entry(first_letter).or_default().push(item),
+            None => other.push(item),
+        }
+    }
+    let mut result: Vec<TimeByLetter> = groups
+        .iter()
+        .map(|(letter, test_suites)| TimeByLetter::new(duration(test_suites.clone()), *letter))
+        .collect();
+    if !other.is_empty() {
+        result.push(TimeByLetter::new(
+            duration(other),
+            policy.other_bucket.clone(),
+        ));
+    }
+    result
+}
+
+/// Names of suites which end up in the other bucket under given policy
+pub fn unusual_first_letters<'a>(
+    test_suites: &'a [TestSuite],
+    policy: &LetterPolicy,
+) -> Vec<&'a str> {
+    test_suites
+        .iter()
+        .filter(|ts| policy.bucket(&ts.name).is_none())
+        .map(|ts| ts.name.as_str())
+        .collect()
+}
+
+pub fn group_by(test_suites: &[TestSuite], grouping: &Grouping) -> Vec<TimeByLetter> {
+    if let Grouping::FirstLetter(policy) = grouping {
+        return group_by_first_letter(test_suites, policy);
+    }
+    let mut groups: BTreeMap<String, Duration> = BTreeMap::new();
+    for (key, time, _) in test_suites.iter().flat_map(|ts| units(ts, grouping)) {
+        *groups.entry(key).or_default() += time;
     }
     groups
+        .into_iter()
+        .map(|(key, time)| TimeByLetter::new(time, key))
+        .collect()
+}
+
+/// Suite, or part of it, behind unit distributed between groups
+#[derive(Debug)]
+pub struct Source<'a> {
+    pub suite: &'a TestSuite,
+    pub test_cases: Vec<&'a TestCase>,
+}
+
+/// Where time of every unit created by [group_by] comes from, by unit key
+pub fn sources<'a>(
+    test_suites: &'a [TestSuite],
+    grouping: &Grouping,
+) -> BTreeMap<String, Vec<Source<'a>>> {
+    let mut result: BTreeMap<String, Vec<Source>> = BTreeMap::new();
+    for suite in test_suites {
+        for (key, _, test_cases) in units(suite, grouping) {
+            result
+                .entry(key)
+                .or_default()
+                .push(Source { suite, test_cases });
+        }
+    }
+    result
+}
+
+/// Keys of units the suite belongs to, with time and test cases for each of them
+fn units<'a>(
+    test_suite: &'a TestSuite,
+    grouping: &Grouping,
+) -> Vec<(String, Duration, Vec<&'a TestCase>)> {
+    let all_cases = || test_suite.test_cases.iter().collect();
+    match grouping {
+        Grouping::FirstLetter(policy) => {
+            let key = policy
+                .bucket(&test_suite.name)
+                .map(String::from)
+                .unwrap_or_else(|| policy.other_bucket.clone());
+            vec![(key, test_suite.time_without_skipped(), all_cases())]
+        }
+        Grouping::TestCase => test_case_units(test_suite),
+        _ => vec![(
+            suite_key(test_suite, grouping),
+            test_suite.time_without_skipped(),
+            all_cases(),
+        )],
+    }
+}
+
+fn suite_key(test_suite: &TestSuite, grouping: &Grouping) -> String {
+    let name = &test_suite.name;
+    let simple_name = simple_name(name);
+    match grouping {
+        Grouping::Package(depth) => {
+            let segments: Vec<&str> = name.split('.').collect();
+            segments[..segments.len() - 1]
+                .iter()
+                .take(*depth)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(".")
+        }
+        Grouping::Module => module(test_suite),
+        Grouping::Prefix(length) => simple_name.chars().take(*length).collect(),
+        Grouping::Regex(regex) => regex
+            .captures(name)
+            .and_then(|c| c.get(1).or_else(|| c.get(0)))
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_else(|| name.clone()),
+        Grouping::Class | Grouping::FirstLetter(_) | Grouping::TestCase => name.clone(),
+    }
+}
+
+/// Directory holding `target` or `build` directory with the report, for Maven and Gradle
+/// layouts. Otherwise the outermost enclosing suite or the top level package.
+fn module(test_suite: &TestSuite) -> String {
+    let components: Vec<&str> = Path::new(&test_suite.file)
+        .iter()
+        .filter_map(|c| c.to_str())
+        .collect();
+    components
         .iter()
-        .map(|(letter, test_suites)| TimeByLetter::new(duration(test_suites), letter.clone()))
+        .rposition(|c| *c == "target" || *c == "build")
+        .filter(|&index| index > 0)
+        .map(|index| components[index - 1].to_string())
+        .or_else(|| test_suite.parents.first().cloned())
+        .unwrap_or_else(|| test_suite.name.split('.').next().unwrap().to_string())
+}
+
+fn test_case_units(test_suite: &TestSuite) -> Vec<(String, Duration, Vec<&TestCase>)> {
+    if test_suite.test_cases.is_empty() {
+        return vec![(test_suite.name.clone(), test_suite.time, vec![])];
+    }
+    test_suite
+        .test_cases
+        .iter()
+        .map(|tc| {
+            let classname = if tc.classname.is_empty() {
+                &test_suite.name
+            } else {
+                &tc.classname
+            };
+            let time = if tc.is_skipped() {
+                Duration::ZERO
+            } else {
+                tc.time
+            };
+            (format!("{}.{}", classname, tc.name), time, vec![tc])
+        })
         .collect()
 }
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::model::Outcome;
     #[test]
     fn empty_duration() {
         //when
         let duration = super::duration(&vec![]);
 
         //then
-        assert_eq!(duration, 0.0);
+        assert_eq!(duration, Duration::ZERO);
     }
     #[test]
     fn non_empty_duration() {
         //given
         let test_suite = TestSuite {
             name: String::new(),
-            time: 1.0,
+            time: Duration::from_secs(1),
             test_cases: vec![],
+            ..Default::default()
         };
 
         //when
         let duration = super::duration(&vec![test_suite]);
 
         //then
-        assert_eq!(duration, 1.0);
+        assert_eq!(duration, Duration::from_secs(1));
     }
 
     #[test]
@@ -101,7 +327,7 @@ mod test {
         //given
 
         //when
-        let result: Vec<TimeByLetter> = group_by_first_letter(vec![]);
+        let result: Vec<TimeByLetter> = group_by_first_letter(&[], &LetterPolicy::default());
 
         //then
         let expected = vec![
@@ -139,18 +365,23 @@ mod test {
         //given
 
         //when
-        let result: Vec<TimeByLetter> = group_by_first_letter(vec![
-            TestSuite {
-                name: String::from("Abrakadabra1"),
-                time: 1.0,
-                test_cases: vec![],
-            },
-            TestSuite {
-                name: String::from("Abrakadabra2"),
-                time: 2.0,
-                test_cases: vec![],
-            },
-        ]);
+        let result: Vec<TimeByLetter> = group_by_first_letter(
+            &[
+                TestSuite {
+                    name: String::from("Abrakadabra1"),
+                    time: Duration::from_secs(1),
+                    test_cases: vec![],
+                    ..Default::default()
+                },
+                TestSuite {
+                    name: String::from("Abrakadabra2"),
+                    time: Duration::from_secs(2),
+                    test_cases: vec![],
+                    ..Default::default()
+                },
+            ],
+            &LetterPolicy::default(),
+        );
 
         //then
         let expected = vec![
@@ -341,4 +572,258 @@ mod test {
         ];
         assert_eq!(result, expected);
     }
+
+    fn suite(name: &str, time: f64) -> TestSuite {
+        TestSuite {
+            name: String::from(name),
+            time: Duration::from_secs_f64(time),
+            ..Default::default()
+        }
+    }
+
+    fn keys(result: &[TimeByLetter]) -> Vec<(&str, f64)> {
+        result
+            .iter()
+            .map(|t| (t.key.as_str(), t.time.as_secs_f64()))
+            .collect()
+    }
+
+    #[test]
+    fn group_by_class() {
+        //when
+        let result = group_by(
+            &[
+                suite("com.foo.BTest", 1.0),
+                suite("com.foo.ATest", 2.0),
+                suite("com.foo.ATest", 3.0),
+            ],
+            &Grouping::Class,
+        );
+
+        //then
+        assert_eq!(keys(&result), vec![("com.foo.ATest", 5.0), ("com.foo.BTest", 1.0)]);
+    }
+
+    #[test]
+    fn group_by_package() {
+        //when
+        let result = group_by(
+            &[
+                suite("com.foo.api.ATest", 1.0),
+                suite("com.foo.db.BTest", 2.0),
+                suite("com.bar.CTest", 3.0),
+                suite("DTest", 4.0),
+            ],
+            &Grouping::Package(2),
+        );
+
+        //then
+        assert_eq!(
+            keys(&result),
+            vec![("", 4.0), ("com.bar", 3.0), ("com.foo", 3.0)]
+        );
+    }
+
+    #[test]
+    fn group_by_module() {
+        //given
+        let mut from_maven = suite("com.foo.ATest", 1.0);
+        from_maven.file = String::from("repo/api/target/surefire-reports/TEST-com.foo.ATest.xml");
+        let mut from_gradle = suite("com.foo.BTest", 2.0);
+        from_gradle.file = String::from("repo/db/build/test-results/test/TEST-com.foo.BTest.xml");
+        let mut nested = suite("com.foo.CTest", 3.0);
+        nested.parents = vec![String::from("web")];
+
+        //when
+        let result = group_by(&[from_maven, from_gradle, nested], &Grouping::Module);
+
+        //then
+        assert_eq!(keys(&result), vec![("api", 1.0), ("db", 2.0), ("web", 3.0)]);
+    }
+
+    #[test]
+    fn group_by_prefix() {
+        //when
+        let result = group_by(
+            &[
+                suite("com.foo.AbcTest", 1.0),
+                suite("com.bar.AbdTest", 2.0),
+                suite("com.foo.AcTest", 3.0),
+            ],
+            &Grouping::Prefix(2),
+        );
+
+        //then
+        assert_eq!(keys(&result), vec![("Ab", 3.0), ("Ac", 3.0)]);
+    }
+
+    #[test]
+    fn group_by_regex() {
+        //when
+        let result = group_by(
+            &[
+                suite("com.foo.smoke.ATest", 1.0),
+                suite("com.foo.smoke.BTest", 2.0),
+                suite("com.foo.slow.CTest", 3.0),
+                suite("other.DTest", 4.0),
+            ],
+            &Grouping::Regex(Regex::new(r"com\.foo\.(\w+)\.").unwrap()),
+        );
+
+        //then
+        assert_eq!(
+            keys(&result),
+            vec![("other.DTest", 4.0), ("slow", 3.0), ("smoke", 3.0)]
+        );
+    }
+
+    #[test]
+    fn group_by_test_case() {
+        //given
+        let mut with_cases = suite("com.foo.ATest", 3.0);
+        with_cases.test_cases = vec![
+            TestCase {
+                name: String::from("second"),
+                time: Duration::from_secs(2),
+                classname: String::from("com.foo.ATest"),
+                ..Default::default()
+            },
+            TestCase {
+                name: String::from("first"),
+                time: Duration::from_secs(1),
+                classname: String::new(),
+                ..Default::default()
+            },
+            TestCase {
+                name: String::from("skipped"),
+                time: Duration::from_secs(1),
+                classname: String::from("com.foo.ATest"),
+                outcome: Outcome::Skipped(Default::default()),
+            },
+        ];
+
+        //when
+        let result = group_by(&[with_cases, suite("com.foo.BTest", 4.0)], &Grouping::TestCase);
+
+        //then
+        assert_eq!(
+            keys(&result),
+            vec![
+                ("com.foo.ATest.first", 1.0),
+                ("com.foo.ATest.second", 2.0),
+                ("com.foo.ATest.skipped", 0.0),
+                ("com.foo.BTest", 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn group_by_first_letter_unusual_names() {
+        //given
+        let suites = vec![
+            suite("com.foo.alphaSpec", 1.0),
+            suite("com.foo.AlphaTest", 2.0),
+            suite("test_module", 3.0),
+            suite("com.foo.1Test", 4.0),
+            suite("", 5.0),
+            suite("com.foo.ÉtéTest", 6.0),
+            suite("com.foo.ЖTest", 7.0),
+        ];
+
+        //when
+        let result = group_by_first_letter(&suites, &LetterPolicy::default());
+
+        //then
+        assert_eq!(result.len(), 27);
+        assert_eq!(keys(&result)[0], ("A", 3.0));
+        assert_eq!(keys(&result)[19], ("T", 3.0));
+        assert_eq!(keys(&result)[26], ("#", 4.0 + 5.0 + 6.0 + 7.0));
+    }
+
+    #[test]
+    fn group_by_first_letter_unicode_case_sensitive() {
+        //given
+        let policy = LetterPolicy {
+            fold_case: false,
+            unicode: true,
+            other_bucket: String::from("other"),
+        };
+        let suites = vec![
+            suite("com.foo.alphaSpec", 1.0),
+            suite("com.foo.AlphaTest", 2.0),
+            suite("com.foo._Test", 3.0),
+            suite("com.foo.ÉtéTest", 6.0),
+            suite("com.foo.ЖTest", 7.0),
+        ];
+
+        //when
+        let result = group_by_first_letter(&suites, &policy);
+
+        //then
+        let non_empty: Vec<(&str, f64)> = keys(&result)
+            .into_iter()
+            .filter(|(_, time)| *time > 0.0)
+            .collect();
+        assert_eq!(
+            non_empty,
+            vec![
+                ("A", 2.0),
+                ("E", 6.0),
+                ("a", 1.0),
+                ("Ж", 7.0),
+                ("other", 3.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn group_by_first_letter_uses_file_name_of_path_named_suites() {
+        //given
+        let suites = vec![
+            suite("src/calc.test.js", 1.0),
+            suite("src/view/render.spec.tsx", 2.0),
+            suite("tests/test_api.py", 3.0),
+            suite("tests\\db\\test_models.py", 4.0),
+            suite("test_views.py", 5.0),
+        ];
+
+        //when
+        let result = group_by_first_letter(&suites, &LetterPolicy::default());
+
+        //then
+        let non_empty: Vec<(&str, f64)> = keys(&result)
+            .into_iter()
+            .filter(|(_, time)| *time > 0.0)
+            .collect();
+        assert_eq!(
+            non_empty,
+            vec![("A", 3.0), ("C", 1.0), ("M", 4.0), ("R", 2.0), ("V", 5.0)]
+        );
+    }
+
+    #[test]
+    fn unusual_first_letters_lists_odd_names() {
+        //given
+        let suites = vec![
+            suite("com.foo.AlphaTest", 1.0),
+            suite("com.foo.alphaSpec", 1.0),
+            suite("", 1.0),
+            suite("com.foo.1Test", 1.0),
+            suite("com.foo.ÉtéTest", 1.0),
+        ];
+
+        //when
+        let result = unusual_first_letters(&suites, &LetterPolicy::default());
+        let unicode = unusual_first_letters(
+            &suites,
+            &LetterPolicy {
+                unicode: true,
+                ..Default::default()
+            },
+        );
+
+        //then
+        assert_eq!(result, vec!["", "com.foo.1Test", "com.foo.ÉtéTest"]);
+        assert_eq!(unicode, vec!["", "com.foo.1Test"]);
+    }
 }
@@ -0,0 +1,564 @@
+use crate::model::TimeByLetter;
+use crate::processing::Source;
+use clap::ValueEnum;
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+/// Syntax of selector printed for a group
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Runner {
+    /// Group members (letters, classes, packages...) separated by space
+    #[default]
+    Keys,
+    /// Fully qualified names of test classes in group separated by space
+    Classes,
+    /// Gradle `--tests` arguments for test classes in group, build has to set
+    /// `failOnNoMatchingTests = false` for shards without tests
+    Gradle,
+    /// Maven Surefire `-Dtest=` list of test classes in group
+    Maven,
+    /// Maven Failsafe `-Dit.test=` list of integration test classes in group
+    Failsafe,
+    /// cargo-nextest filterset expression for `-E`, selecting test binaries or single tests
+    Nextest,
+    /// pytest node ids of test files or single tests in group
+    Pytest,
+    /// Test files of group separated by space, for Jest, Vitest, Mocha or Playwright arguments
+    Files,
+    /// Jest positional regex matching test files of group
+    Jest,
+    /// Cypress `--spec` list of test files of group
+    Cypress,
+}
+
+/// One of `count` shards, written as `index/count` with index starting from 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShardSpec {
+    pub index: u16,
+    pub count: u16,
+}
+
+impl FromStr for ShardSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, count) = s
+            .split_once('/')
+            .ok_or_else(|| format!("expected index/count, got {}", s))?;
+        let index: u16 = index
+            .trim()
+            .parse()
+            .map_err(|e| format!("invalid shard index {}: {}", index, e))?;
+        let count: u16 = count
+            .trim()
+            .parse()
+            .map_err(|e| format!("invalid shard count {}: {}", count, e))?;
+        if index == 0 || index > count {
+            return Err(format!("shard index has to be between 1 and {}", count));
+        }
+        Ok(ShardSpec { index, count })
+    }
+}
+
+pub fn selector(
+    runner: Runner,
+    members: &[TimeByLetter],
+    sources: &BTreeMap<String, Vec<Source>>,
+) -> String {
+    match runner {
+        Runner::Keys => members
+            .iter()
+            .map(|tbl| tbl.key.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+        Runner::Classes => classes(members, sources)
+            .into_iter()
+            .collect::<Vec<_>>()
+            .join(" "),
+        Runner::Gradle => gradle_args(classes(members, sources)),
+        Runner::Maven => maven_args(classes(members, sources), "test", "surefire"),
+        Runner::Failsafe => maven_args(classes(members, sources), "it.test", "failsafe"),
+        Runner::Nextest => nextest_filterset(members, sources),
+        Runner::Pytest => pytest_node_ids(members, sources)
+            .iter()
+            .map(|id| shell_quote(id))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Runner::Files => files(members, sources)
+            .into_iter()
+            .map(shell_quote)
+            .collect::<Vec<_>>()
+            .join(" "),
+        Runner::Jest => jest_args(files(members, sources)),
+        Runner::Cypress => match files(members, sources) {
+            files if files.is_empty() => String::new(),
+            files => format!(
+                "--spec {}",
+                shell_quote(&files.into_iter().collect::<Vec<_>>().join(","))
+            ),
+        },
+    }
+}
+
+/// Test files behind members, report formats of JavaScript runners name suites by file
+fn files<'a>(
+    members: &[TimeByLetter],
+    sources: &'a BTreeMap<String, Vec<Source>>,
+) -> BTreeSet<&'a str> {
+    members
+        .iter()
+        .filter_map(|tbl| sources.get(&tbl.key))
+        .flatten()
+        .map(|source| source.suite.name.as_str())
+        .collect()
+}
+
+/// Pattern is passed as positional argument, which every Jest version accepts, while
+/// `--testPathPattern` was renamed in Jest 30. It is matched against absolute path, so only end
+/// of path is anchored. Empty group matches nothing, `--passWithNoTests` keeps such shard green.
+fn jest_args<'a>(files: impl IntoIterator<Item = &'a str>) -> String {
+    let alternatives: Vec<String> = files.into_iter().map(regex::escape).collect();
+    let pattern = match alternatives.is_empty() {
+        true => String::from("^$"),
+        false => format!("({})$", alternatives.join("|")),
+    };
+    format!("--passWithNoTests {}", shell_quote(&pattern))
+}
+
+/// Path of test module with classes, like `tests/test_api.py::TestPost`. Report log already
+/// uses file paths, JUnit reports of pytest use dotted module path followed by class names.
+fn pytest_path(classname: &str) -> String {
+    if classname.ends_with(".py") || classname.contains(".py::") {
+        return classname.to_string();
+    }
+    let segments: Vec<&str> = classname.split('.').collect();
+    let classes_from = segments
+        .iter()
+        .position(|segment| segment.starts_with(char::is_uppercase))
+        .unwrap_or(segments.len());
+    let mut path = format!("{}.py", segments[..classes_from].join("/"));
+    segments[classes_from..]
+        .iter()
+        .for_each(|class| path.push_str(&format!("::{}", class)));
+    path
+}
+
+/// Node ids for `pytest` arguments, whole files when every test of file is in group
+pub fn pytest_node_ids(
+    members: &[TimeByLetter],
+    sources: &BTreeMap<String, Vec<Source>>,
+) -> BTreeSet<String> {
+    members
+        .iter()
+        .filter_map(|tbl| sources.get(&tbl.key))
+        .flatten()
+        .flat_map(|source| {
+            let whole = source.test_cases.len() == source.suite.test_cases.len();
+            if whole && (source.suite.name.ends_with(".py") || source.test_cases.is_empty()) {
+                vec![pytest_path(&source.suite.name)]
+            } else {
+                source
+                    .test_cases
+                    .iter()
+                    .map(|tc| match tc.classname.as_str() {
+                        "" => tc.name.clone(),
+                        classname => format!("{}::{}", pytest_path(classname), tc.name),
+                    })
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+/// Whole binaries are selected by `binary_id`, single tests by `test` qualified with binary,
+/// as the same test path can exist in many binaries
+fn nextest_filterset(members: &[TimeByLetter], sources: &BTreeMap<String, Vec<Source>>) -> String {
+    let escape = |value: &str| value.replace('\\', "\\\\").replace(')', "\\)");
+    let expressions: BTreeSet<String> = members
+        .iter()
+        .filter_map(|tbl| sources.get(&tbl.key))
+        .flatten()
+        .flat_map(|source| {
+            let binary = escape(&source.suite.name);
+            if source.test_cases.len() == source.suite.test_cases.len() {
+                vec![format!("binary_id(={})", binary)]
+            } else {
+                source
+                    .test_cases
+                    .iter()
+                    .map(|tc| format!("(binary_id(={}) & test(={}))", binary, escape(&tc.name)))
+                    .collect()
+            }
+        })
+        .collect();
+    if expressions.is_empty() {
+        String::from("none()")
+    } else {
+        expressions.into_iter().collect::<Vec<_>>().join(" | ")
+    }
+}
+
+/// Quotes value for POSIX shell, unless it consists only of safe characters
+fn shell_quote(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "._-/*:=@,+".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Class which does not exist, used when empty filter would run every test
+pub const NO_TESTS_CLASS: &str = "no.tests.in.this.Shard";
+
+/// Outer class of nested `Outer$Inner` class, filters of build tools do not match binary names
+/// and nested classes run together with their outer class anyway
+fn outer_class(class: &str) -> &str {
+    class.split('$').next().unwrap()
+}
+
+/// Empty group selects [NO_TESTS_CLASS], as no `--tests` would run every test. Gradle fails when
+/// `--tests` matches nothing, unless the build sets `filter.failOnNoMatchingTests = false`, as
+/// scripts of [gradle_init_script] do.
+fn gradle_args<'a>(classes: impl IntoIterator<Item = &'a str>) -> String {
+    let mut classes: BTreeSet<&str> = classes.into_iter().map(outer_class).collect();
+    if classes.is_empty() {
+        classes.insert(NO_TESTS_CLASS);
+    }
+    classes
+        .into_iter()
+        .map(|class| format!("--tests {}", shell_quote(class)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Init script restricting every `Test` task to given classes, for `gradle --init-script`.
+/// Projects without any of these classes do not fail, as they would with `--tests`.
+pub fn gradle_init_script<'a>(classes: impl IntoIterator<Item = &'a str>) -> String {
+    let mut result = String::from(concat!(
+        "allprojects {\n",
+        "    tasks.withType(Test).configureEach {\n",
+        "        filter {\n",
+        "            failOnNoMatchingTests = false\n",
+    ));
+    let classes: BTreeSet<&str> = classes.into_iter().map(outer_class).collect();
+    if classes.is_empty() {
+        // Filter without patterns would run every test
+        result.push_str(&format!(
+            "            includeTestsMatching '{}'\n",
+            NO_TESTS_CLASS
+        ));
+    }
+    classes.into_iter().for_each(|class| {
+        result.push_str(&format!(
+            "            includeTestsMatching '{}'\n",
+            class.replace('\\', "\\\\").replace('\'', "\\'")
+        ))
+    });
+    result.push_str("        }\n    }\n}\n");
+    result
+}
+
+/// Modules without any of the classes would fail without `failIfNoSpecifiedTests` of `plugin`
+fn maven_args<'a>(
+    classes: impl IntoIterator<Item = &'a str>,
+    property: &str,
+    plugin: &str,
+) -> String {
+    let classes: BTreeSet<&str> = classes.into_iter().map(outer_class).collect();
+    let list = if classes.is_empty() {
+        String::from(NO_TESTS_CLASS)
+    } else {
+        classes.into_iter().collect::<Vec<_>>().join(",")
+    };
+    format!(
+        "{} -D{}.failIfNoSpecifiedTests=false",
+        shell_quote(&format!("-D{}={}", property, list)),
+        plugin
+    )
+}
+
+/// Line of Surefire `includesFile` or `excludesFile` matching given class
+pub fn maven_pattern(class: &str) -> String {
+    format!("{}.java", outer_class(class).replace('.', "/"))
+}
+
+/// Test classes behind members, taken from test cases or suite name when there are none
+pub fn classes<'a>(
+    members: &[TimeByLetter],
+    sources: &'a BTreeMap<String, Vec<Source>>,
+) -> BTreeSet<&'a str> {
+    members
+        .iter()
+        .filter_map(|tbl| sources.get(&tbl.key))
+        .flatten()
+        .flat_map(|source| {
+            let from_cases: Vec<&str> = source
+                .test_cases
+                .iter()
+                .map(|tc| tc.classname.as_str())
+                .filter(|classname| !classname.is_empty())
+                .collect();
+            if from_cases.is_empty() {
+                vec![source.suite.name.as_str()]
+            } else {
+                from_cases
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::duration::Duration;
+    use crate::model::{TestCase, TestSuite};
+    use crate::processing::{self, Grouping, LetterPolicy};
+
+    #[test]
+    fn parse_shard_spec() {
+        //then
+        assert_eq!(
+            "2/5".parse::<ShardSpec>(),
+            Ok(ShardSpec { index: 2, count: 5 })
+        );
+        assert!("0/5".parse::<ShardSpec>().is_err());
+        assert!("6/5".parse::<ShardSpec>().is_err());
+        assert!("5".parse::<ShardSpec>().is_err());
+        assert!("a/5".parse::<ShardSpec>().is_err());
+    }
+
+    #[test]
+    fn selectors() {
+        //given
+        let suites = vec![
+            TestSuite {
+                name: String::from("com.foo.BTest"),
+                time: Duration::from_secs(1),
+                ..Default::default()
+            },
+            TestSuite {
+                name: String::from("com.foo.ATest"),
+                time: Duration::from_secs(1),
+                test_cases: vec![TestCase {
+                    name: String::from("works"),
+                    classname: String::from("com.foo.ATest$Nested"),
+                    time: Duration::from_secs(1),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ];
+        let grouping = Grouping::FirstLetter(LetterPolicy::default());
+        let sources = processing::sources(&suites, &grouping);
+        let members = vec![TimeByLetter::new(1.0, 'A'), TimeByLetter::new(1.0, 'B')];
+
+        //when
+        let keys = selector(Runner::Keys, &members, &sources);
+        let classes = selector(Runner::Classes, &members, &sources);
+
+        //then
+        assert_eq!(keys, "A B");
+        assert_eq!(classes, "com.foo.ATest$Nested com.foo.BTest");
+    }
+
+    #[test]
+    fn gradle_selectors() {
+        //given
+        let classes = vec![
+            "com.foo.BTest",
+            "com.foo.ATest$Nested",
+            "com.foo.ATest",
+            "it's",
+        ];
+
+        //when
+        let args = gradle_args(classes.clone());
+        let empty = gradle_args(vec![]);
+        let script = gradle_init_script(classes);
+
+        //then
+        assert_eq!(
+            args,
+            r#"--tests com.foo.ATest --tests com.foo.BTest --tests 'it'\''s'"#
+        );
+        assert_eq!(empty, "--tests no.tests.in.this.Shard");
+        assert_eq!(
+            script,
+            r#"allprojects {
+    tasks.withType(Test).configureEach {
+        filter {
+            failOnNoMatchingTests = false
+            includeTestsMatching 'com.foo.ATest'
+            includeTestsMatching 'com.foo.BTest'
+            includeTestsMatching 'it\'s'
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn maven_selectors() {
+        //given
+        let classes = vec!["com.foo.BTest", "com.foo.ATest$Nested", "com.foo.ATest"];
+
+        //when
+        let args = maven_args(classes.clone(), "test", "surefire");
+        let empty = maven_args(vec![], "test", "surefire");
+        let failsafe = maven_args(classes, "it.test", "failsafe");
+
+        //then
+        assert_eq!(
+            args,
+            "-Dtest=com.foo.ATest,com.foo.BTest -Dsurefire.failIfNoSpecifiedTests=false"
+        );
+        assert_eq!(
+            empty,
+            "-Dtest=no.tests.in.this.Shard -Dsurefire.failIfNoSpecifiedTests=false"
+        );
+        assert_eq!(
+            failsafe,
+            "-Dit.test=com.foo.ATest,com.foo.BTest -Dfailsafe.failIfNoSpecifiedTests=false"
+        );
+        assert_eq!(maven_pattern("com.foo.ATest$Nested"), "com/foo/ATest.java");
+    }
+
+    #[test]
+    fn nextest_selectors() {
+        //given
+        let case = |name: &str| TestCase {
+            name: String::from(name),
+            classname: String::from("my-crate"),
+            time: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let suites = vec![
+            TestSuite {
+                name: String::from("my-crate"),
+                time: Duration::from_secs(2),
+                test_cases: vec![case("tests::a"), case("tests::b")],
+                ..Default::default()
+            },
+            TestSuite {
+                name: String::from("my-crate::bin/tool"),
+                time: Duration::from_secs(1),
+                test_cases: vec![case("cli_works")],
+                ..Default::default()
+            },
+        ];
+        let by_binary = processing::sources(&suites, &Grouping::Class);
+        let by_test = processing::sources(&suites, &Grouping::TestCase);
+
+        //when
+        let binaries = selector(
+            Runner::Nextest,
+            &[
+                TimeByLetter::new(2.0, "my-crate"),
+                TimeByLetter::new(1.0, "my-crate::bin/tool"),
+            ],
+            &by_binary,
+        );
+        let tests = selector(
+            Runner::Nextest,
+            &[TimeByLetter::new(1.0, "my-crate.tests::b")],
+            &by_test,
+        );
+        let empty = selector(Runner::Nextest, &[], &by_test);
+
+        //then
+        assert_eq!(
+            binaries,
+            "binary_id(=my-crate) | binary_id(=my-crate::bin/tool)"
+        );
+        assert_eq!(tests, "(binary_id(=my-crate) & test(=tests::b))");
+        assert_eq!(empty, "none()");
+    }
+
+    #[test]
+    fn pytest_selectors() {
+        //given
+        let case = |classname: &str, name: &str| TestCase {
+            name: String::from(name),
+            classname: String::from(classname),
+            time: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let suites = vec![
+            TestSuite {
+                name: String::from("tests/test_api.py"),
+                time: Duration::from_secs(2),
+                test_cases: vec![
+                    case("tests/test_api.py", "test_get"),
+                    case("tests/test_api.py", "TestPost::test_post[json]"),
+                ],
+                ..Default::default()
+            },
+            TestSuite {
+                name: String::from("pytest"),
+                time: Duration::from_secs(1),
+                test_cases: vec![case("tests.test_db.TestQuery", "test_select")],
+                ..Default::default()
+            },
+        ];
+        let by_file = processing::sources(&suites, &Grouping::Class);
+        let by_test = processing::sources(&suites, &Grouping::TestCase);
+
+        //when
+        let files = selector(
+            Runner::Pytest,
+            &[
+                TimeByLetter::new(2.0, "tests/test_api.py"),
+                TimeByLetter::new(1.0, "pytest"),
+            ],
+            &by_file,
+        );
+        let tests = selector(
+            Runner::Pytest,
+            &[TimeByLetter::new(
+                1.0,
+                "tests/test_api.py.TestPost::test_post[json]",
+            )],
+            &by_test,
+        );
+
+        //then
+        assert_eq!(
+            files,
+            "tests/test_api.py tests/test_db.py::TestQuery::test_select"
+        );
+        assert_eq!(tests, "'tests/test_api.py::TestPost::test_post[json]'");
+    }
+
+    #[test]
+    fn javascript_selectors() {
+        //given
+        let suite = |name: &str| TestSuite {
+            name: String::from(name),
+            time: Duration::from_secs(1),
+            ..Default::default()
+        };
+        let suites = vec![suite("src/calc.test.js"), suite("src/app.test.tsx")];
+        let sources = processing::sources(&suites, &Grouping::Class);
+        let members = vec![
+            TimeByLetter::new(1.0, "src/calc.test.js"),
+            TimeByLetter::new(1.0, "src/app.test.tsx"),
+        ];
+
+        //when
+        let files = selector(Runner::Files, &members, &sources);
+        let jest = selector(Runner::Jest, &members, &sources);
+        let cypress = selector(Runner::Cypress, &members, &sources);
+        let empty = selector(Runner::Jest, &[], &sources);
+
+        //then
+        assert_eq!(files, "src/app.test.tsx src/calc.test.js");
+        assert_eq!(
+            jest,
+            r"--passWithNoTests '(src/app\.test\.tsx|src/calc\.test\.js)$'"
+        );
+        assert_eq!(cypress, "--spec src/app.test.tsx,src/calc.test.js");
+        assert_eq!(empty, "--passWithNoTests '^$'");
+    }
+}
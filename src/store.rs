@@ -0,0 +1,418 @@
+use crate::duration::Duration;
+use crate::error::ReportError;
+use crate::estimator::Estimation;
+use crate::model::{Outcome, OutcomeDetails, TestCase, TestSuite};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Run of tests whose reports are recorded together
+#[derive(Debug, Clone)]
+pub struct Run {
+    pub id: String,
+    pub commit: Option<String>,
+    /// Seconds since Unix epoch
+    pub timestamp: u64,
+}
+
+/// One line of timing store: timings of one suite in one run
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SuiteRecord {
+    pub run: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub timestamp: u64,
+    pub suite: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<String>,
+    #[serde(default)]
+    pub file: String,
+    pub time: Duration,
+    #[serde(default)]
+    pub test_cases: Vec<CaseRecord>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CaseRecord {
+    pub name: String,
+    #[serde(default)]
+    pub classname: String,
+    pub time: Duration,
+    /// See [Outcome::name]
+    pub outcome: String,
+}
+
+impl SuiteRecord {
+    pub fn new(run: &Run, suite: &TestSuite) -> Self {
+        SuiteRecord {
+            run: run.id.clone(),
+            commit: run.commit.clone(),
+            timestamp: run.timestamp,
+            suite: suite.name.clone(),
+            parents: suite.parents.clone(),
+            file: suite.file.clone(),
+            time: suite.time,
+            test_cases: suite
+                .test_cases
+                .iter()
+                .map(|tc| CaseRecord {
+                    name: tc.name.clone(),
+                    classname: tc.classname.clone(),
+                    time: tc.time,
+                    outcome: tc.outcome.name().to_string(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Appends suites as records of given run, one JSON object per line. Creates the store and its
+/// directory when they do not exist yet.
+pub fn record(path: &Path, run: &Run, test_suites: &[TestSuite]) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut lines = String::new();
+    for suite in test_suites {
+        lines.push_str(&serde_json::to_string(&SuiteRecord::new(run, suite))?);
+        lines.push('\n');
+    }
+    // Written at once, so interrupted run does not leave half of its suites behind
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(lines.as_bytes())
+}
+
+/// Reads all records of the store. Lines which are not valid records are returned separately,
+/// the rest of the store is still used.
+pub fn load(path: &Path) -> Result<(Vec<SuiteRecord>, Vec<ReportError>), ReportError> {
+    let display = path.to_string_lossy().into_owned();
+    let content = fs::read_to_string(path).map_err(|source| ReportError::Read {
+        path: display.clone(),
+        source,
+    })?;
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(record) => records.push(record),
+            Err(e) => errors.push(ReportError::Record {
+                path: display.clone(),
+                line: index + 1,
+                message: e.to_string(),
+            }),
+        }
+    }
+    Ok((records, errors))
+}
+
+/// Turns records of all runs into one report with estimated time of every suite and test case,
+/// taken over runs which contain it. Outcome and file come from the latest run. Recording the
+/// same run again replaces all its earlier records.
+pub fn aggregate(records: Vec<SuiteRecord>, estimation: &Estimation) -> Vec<TestSuite> {
+    let appends = appends(&records);
+    let latest: HashMap<&str, usize> = records
+        .iter()
+        .zip(&appends)
+        .map(|(r, append)| (r.run.as_str(), *append))
+        .collect();
+    let kept: Vec<bool> = records
+        .iter()
+        .zip(&appends)
+        .map(|(r, append)| latest[r.run.as_str()] == *append)
+        .collect();
+    let mut records: Vec<(usize, SuiteRecord)> = records
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| kept[*index])
+        .collect();
+    // Oldest first, records of the same time keep order of the store
+    records.sort_by_key(|(index, r)| (r.timestamp, *index));
+    let mut suites: BTreeMap<(Vec<String>, String), Vec<SuiteRecord>> = BTreeMap::new();
+    for (_, record) in records {
+        suites
+            .entry((record.parents.clone(), record.suite.clone()))
+            .or_default()
+            .push(record);
+    }
+    suites
+        .into_iter()
+        .map(|((parents, name), runs)| aggregate_suite(parents, name, runs, estimation))
+        .collect()
+}
+
+/// Position of the first record of append which wrote each record. One append writes records of
+/// a single run next to each other, so the next one starts with another run or a repeated suite.
+fn appends(records: &[SuiteRecord]) -> Vec<usize> {
+    let mut start = 0;
+    let mut suites = HashSet::new();
+    records
+        .iter()
+        .enumerate()
+        .map(|(index, r)| {
+            let key = (&r.parents, &r.suite);
+            if index == 0 || records[start].run != r.run || suites.contains(&key) {
+                start = index;
+                suites.clear();
+            }
+            suites.insert(key);
+            start
+        })
+        .collect()
+}
+
+fn aggregate_suite(
+    parents: Vec<String>,
+    name: String,
+    runs: Vec<SuiteRecord>,
+    estimation: &Estimation,
+) -> TestSuite {
+    let times: Vec<Duration> = runs.iter().map(|r| r.time).collect();
+    let time = estimation.estimate(&times);
+    let file = runs.last().map(|r| r.file.clone()).unwrap_or_default();
+    let mut order: Vec<(String, String)> = Vec::new();
+    let mut cases: HashMap<(String, String), (Vec<Duration>, String)> = HashMap::new();
+    for case in runs.into_iter().flat_map(|r| r.test_cases) {
+        let key = (case.classname, case.name);
+        let (times, outcome) = cases.entry(key.clone()).or_insert_with(|| {
+            order.push(key);
+            (Vec::new(), String::new())
+        });
+        times.push(case.time);
+        *outcome = case.outcome;
+    }
+    let test_cases = order
+        .into_iter()
+        .map(|key| {
+            let (times, outcome) = cases.remove(&key).unwrap();
+            TestCase {
+                classname: key.0,
+                name: key.1,
+                time: estimation.estimate(&times),
+                outcome: outcome_from_name(&outcome),
+            }
+        })
+        .collect();
+    TestSuite {
+        name,
+        time,
+        test_cases,
+        parents,
+        file,
+        ..Default::default()
+    }
+}
+
+/// Reverse of [Outcome::name], details are not recorded
+fn outcome_from_name(name: &str) -> Outcome {
+    match name {
+        "failed" => Outcome::Failed(OutcomeDetails::default()),
+        "errored" => Outcome::Errored(OutcomeDetails::default()),
+        "skipped" => Outcome::Skipped(OutcomeDetails::default()),
+        _ => Outcome::Passed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::estimator::Estimator;
+    use tempfile::tempdir;
+
+    fn run(id: &str, timestamp: u64) -> Run {
+        Run {
+            id: String::from(id),
+            commit: Some(String::from("abc123")),
+            timestamp,
+        }
+    }
+
+    fn suite(name: &str, cases: &[(&str, f64, &str)]) -> TestSuite {
+        TestSuite {
+            name: String::from(name),
+            time: cases.iter().map(|c| Duration::from_secs_f64(c.1)).sum(),
+            file: format!("build/TEST-{}.xml", name),
+            test_cases: cases
+                .iter()
+                .map(|(case, time, outcome)| TestCase {
+                    name: case.to_string(),
+                    classname: String::from(name),
+                    time: Duration::from_secs_f64(*time),
+                    outcome: outcome_from_name(outcome),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn record_and_load() {
+        //given
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history/timings.jsonl");
+        let suites = vec![
+            suite("a.ATest", &[("works", 1.5, "passed")]),
+            suite("a.BTest", &[]),
+        ];
+
+        //when
+        record(&path, &run("1", 100), &suites).unwrap();
+        record(&path, &run("2", 200), &suites[..1]).unwrap();
+        let (records, errors) = load(&path).unwrap();
+
+        //then
+        assert!(errors.is_empty());
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0], SuiteRecord::new(&run("1", 100), &suites[0]));
+        assert_eq!(records[2].run, "2");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap().lines().nth(1).unwrap(),
+            r#"{"run":"1","commit":"abc123","timestamp":100,"suite":"a.BTest","file":"build/TEST-a.BTest.xml","time":0.0,"test_cases":[]}"#
+        );
+    }
+
+    #[test]
+    fn load_skips_invalid_lines() {
+        //given
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("timings.jsonl");
+        fs::write(
+            &path,
+            "{\"run\":\"1\",\"timestamp\":1,\"suite\":\"a.ATest\",\"time\":2.5}\n\n{\"run\":\"2\"}\n",
+        )
+        .unwrap();
+
+        //when
+        let (records, errors) = load(&path).unwrap();
+
+        //then
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].time, Duration::from_secs_f64(2.5));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .to_string()
+            .contains("timings.jsonl:3: invalid timing record: missing field `timestamp`"));
+    }
+
+    #[test]
+    fn load_missing_store() {
+        //when
+        let result = load(Path::new("non_existent_store.jsonl"));
+
+        //then
+        assert!(matches!(result, Err(ReportError::Read { .. })));
+    }
+
+    #[test]
+    fn aggregate_means_over_runs() {
+        //given
+        let first = suite(
+            "a.ATest",
+            &[("slow", 4.0, "passed"), ("flaky", 1.0, "passed")],
+        );
+        let second = suite(
+            "a.ATest",
+            &[
+                ("slow", 6.0, "passed"),
+                ("flaky", 2.0, "failed"),
+                ("new", 3.0, "passed"),
+            ],
+        );
+        let other = suite("b.BTest", &[("works", 1.0, "passed")]);
+        let records = vec![
+            SuiteRecord::new(&run("2", 200), &second),
+            SuiteRecord::new(&run("1", 100), &first),
+            SuiteRecord::new(&run("1", 100), &other),
+        ];
+
+        //when
+        let result = aggregate(records, &Estimation::default());
+
+        //then
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "a.ATest");
+        assert_eq!(result[0].time, Duration::from_secs(8));
+        assert_eq!(result[0].file, "build/TEST-a.ATest.xml");
+        let cases: Vec<(&str, Duration, &str)> = result[0]
+            .test_cases
+            .iter()
+            .map(|tc| (tc.name.as_str(), tc.time, tc.outcome.name()))
+            .collect();
+        assert_eq!(
+            cases,
+            vec![
+                ("slow", Duration::from_secs(5), "passed"),
+                ("flaky", Duration::from_secs_f64(1.5), "failed"),
+                ("new", Duration::from_secs(3), "passed"),
+            ]
+        );
+        assert_eq!(result[1].time, Duration::from_secs(1));
+    }
+
+    #[test]
+    fn aggregate_replaces_recorded_run() {
+        //given
+        let records = vec![
+            SuiteRecord::new(&run("1", 100), &suite("a.ATest", &[("t", 9.0, "passed")])),
+            SuiteRecord::new(&run("2", 200), &suite("a.ATest", &[("t", 2.0, "passed")])),
+            SuiteRecord::new(&run("1", 100), &suite("a.ATest", &[("t", 4.0, "passed")])),
+        ];
+
+        //when
+        let result = aggregate(records, &Estimation::default());
+
+        //then
+        assert_eq!(result[0].time, Duration::from_secs(3));
+        assert_eq!(result[0].test_cases[0].time, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn aggregate_drops_suites_missing_from_recorded_again_run() {
+        //given
+        let (a, b) = (suite("a.ATest", &[]), suite("b.BTest", &[]));
+        let records = vec![
+            SuiteRecord::new(&run("1", 100), &a),
+            SuiteRecord::new(&run("1", 100), &b),
+            SuiteRecord::new(&run("2", 200), &a),
+            SuiteRecord::new(&run("1", 100), &a),
+            SuiteRecord::new(&run("1", 100), &a),
+        ];
+
+        //when
+        let appends = appends(&records);
+        let result = aggregate(records, &Estimation::default());
+
+        //then
+        assert_eq!(appends, vec![0, 0, 2, 3, 4]);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name, "a.ATest");
+    }
+
+    #[test]
+    fn aggregate_orders_runs_by_timestamp() {
+        //given
+        let records = vec![
+            SuiteRecord::new(&run("3", 300), &suite("a.ATest", &[])),
+            SuiteRecord::new(&run("1", 100), &suite("a.ATest", &[("t", 1.0, "passed")])),
+            SuiteRecord::new(&run("2", 200), &suite("a.ATest", &[("t", 2.0, "passed")])),
+        ];
+        let last = Estimation {
+            estimator: Estimator::Last,
+            ..Default::default()
+        };
+
+        //when
+        let result = aggregate(records, &last);
+
+        //then
+        assert_eq!(result[0].time, Duration::ZERO);
+        assert_eq!(result[0].test_cases[0].time, Duration::from_secs(2));
+    }
+}
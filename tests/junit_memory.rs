@@ -0,0 +1,53 @@
+//! Runs the binary on a report with huge outputs and checks its peak memory. Own test binary, as
+//! measuring memory of unit tests is disturbed by other tests running in parallel.
+#![cfg(target_os = "linux")]
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::Command;
+use tempfile::tempdir;
+
+/// Size of each output in report
+const OUTPUT: u64 = 32 << 20;
+
+/// Largest resident set size of finished child processes, in bytes
+fn children_peak_memory() -> u64 {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    assert_eq!(
+        unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) },
+        0
+    );
+    usage.ru_maxrss as u64 * 1024
+}
+
+#[test]
+fn junit_outputs_are_not_kept_in_memory() {
+    //given
+    let dir = tempdir().unwrap();
+    let mut report = BufWriter::new(File::create(dir.path().join("TEST-a.xml")).unwrap());
+    let parts = [
+        r#"<testsuite name="a.ATest" time="1"><testcase name="t" classname="a.ATest" time="1"><failure message="m"><![CDATA["#,
+        "]]></failure><system-out><![CDATA[",
+        "]]></system-out></testcase><system-err>",
+        "</system-err></testsuite>",
+    ];
+    for (index, part) in parts.iter().enumerate() {
+        if index > 0 {
+            io::copy(&mut io::repeat(b'x').take(OUTPUT), &mut report).unwrap();
+        }
+        report.write_all(part.as_bytes()).unwrap();
+    }
+    report.flush().unwrap();
+
+    //when
+    let output = Command::new(env!("CARGO_BIN_EXE_test-duration-analyzer"))
+        .arg(dir.path())
+        .output()
+        .unwrap();
+
+    //then
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains(" - A: 1s"));
+    let peak = children_peak_memory();
+    assert!(peak < OUTPUT, "peak memory {} bytes", peak);
+}